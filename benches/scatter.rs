@@ -0,0 +1,129 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Compares hashing scattered buffers in place with HasherVectored against
+//! first collecting them into one `Vec`.
+//!
+//! Run with `cargo bench --bench scatter`. Each case hashes a 64 KiB message
+//! kept as 1500 byte segments, about a packet each, and prints the time per
+//! message and the throughput.
+//!
+// Benchmarks are only run on a current toolchain, so black_box() needing a
+// newer Rust than the crate is fine here.
+#![allow(clippy::incompatible_msrv)]
+
+extern crate djb_hash;
+
+use djb_hash::x33a_u32::X33aU32;
+use djb_hash::HasherVectored;
+use std::collections::VecDeque;
+use std::hash::Hasher;
+use std::hint::black_box;
+use std::io::IoSlice;
+use std::time::{Duration, Instant};
+
+const MESSAGE: usize = 64 * 1024;
+const SEGMENT: usize = 1500;
+const ROUNDS: u32 = 2000;
+
+fn bench<F: FnMut() -> u64>(name: &str, mut f: F) -> u64 {
+    for _ in 0..ROUNDS / 10 {
+        black_box(f());
+    }
+    let start = Instant::now();
+    let mut hash = 0;
+    for _ in 0..ROUNDS {
+        hash = black_box(f());
+    }
+    let per = start.elapsed() / ROUNDS;
+    let rate = MESSAGE as f64 / per.max(Duration::from_nanos(1)).as_secs_f64() / 1e6;
+    println!(
+        "{:<24} {:>10.2?} per message {:>10.1} MB/s",
+        name, per, rate
+    );
+    hash
+}
+
+fn main() {
+    let data: Vec<u8> = (0..MESSAGE).map(|i| (i % 251) as u8).collect();
+    let segments: Vec<Vec<u8>> = data.chunks(SEGMENT).map(|c| c.to_vec()).collect();
+    let io_slices: Vec<IoSlice> = segments.iter().map(|s| IoSlice::new(s)).collect();
+    let mut ring: VecDeque<u8> = VecDeque::with_capacity(MESSAGE);
+    ring.extend(&data[..MESSAGE / 2]);
+    ring.extend(&data[..MESSAGE / 2]);
+    ring.drain(..MESSAGE / 2);
+    ring.extend(&data[MESSAGE / 2..]);
+
+    let expected = bench("contiguous", || {
+        let mut hasher = X33aU32::new();
+        hasher.write(black_box(&data));
+        hasher.finish()
+    });
+    let results = [
+        bench("io slices", || {
+            let mut hasher = X33aU32::new();
+            hasher.write_io_slices(black_box(&io_slices));
+            hasher.finish()
+        }),
+        bench("io slices collected", || {
+            let mut buffer = Vec::new();
+            for slice in black_box(&io_slices) {
+                buffer.extend_from_slice(slice);
+            }
+            let mut hasher = X33aU32::new();
+            hasher.write(&buffer);
+            hasher.finish()
+        }),
+        bench("segments", || {
+            let mut hasher = X33aU32::new();
+            hasher.write_slices(black_box(&segments).iter().map(|s| &s[..]));
+            hasher.finish()
+        }),
+        bench("segments collected", || {
+            let buffer: Vec<u8> = black_box(&segments).concat();
+            let mut hasher = X33aU32::new();
+            hasher.write(&buffer);
+            hasher.finish()
+        }),
+        bench("vec deque", || {
+            let mut hasher = X33aU32::new();
+            hasher.write_vec_deque(black_box(&ring));
+            hasher.finish()
+        }),
+        bench("vec deque collected", || {
+            let buffer: Vec<u8> = black_box(&ring).iter().cloned().collect();
+            let mut hasher = X33aU32::new();
+            hasher.write(&buffer);
+            hasher.finish()
+        }),
+    ];
+    for &hash in results.iter() {
+        assert_eq!(hash, expected);
+    }
+}
@@ -0,0 +1,295 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Procedural macros for the djb_hash crate.
+//!
+//! # Examples
+//!
+//! `djb_phf_map!` lays out a perfect hash map at compile time. At run time a
+//! lookup is a single salted X33aU32 hash of the key, one probe and one string
+//! compare.
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate djb_hash_macros;
+//! extern crate djb_hash;
+//! use djb_hash::phf::PhfMap;
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Method {
+//!     Get,
+//!     Head,
+//!     Post,
+//! }
+//!
+//! static METHODS: PhfMap<Method> = djb_phf_map! {
+//!     "GET" => Method::Get,
+//!     "HEAD" => Method::Head,
+//!     "POST" => Method::Post,
+//! };
+//!
+//! fn main() {
+//!     assert_eq!(METHODS.get("POST"), Some(&Method::Post));
+//!     assert_eq!(METHODS.get("PUT"), None);
+//! }
+//! ```
+//!
+//! `#[derive(DjbHash)]` implements `djb_hash::DjbHash` for structs and enums
+//! so they hash to the same value on every platform and Rust version:
+//!
+//! ```rust
+//! #[macro_use]
+//! extern crate djb_hash_macros;
+//! extern crate djb_hash;
+//! use std::hash::Hasher;
+//! use djb_hash::DjbHash;
+//! use djb_hash::x33a_u32::X33aU32;
+//!
+//! #[derive(DjbHash)]
+//! struct Header {
+//!     name: String,
+//!     value: String,
+//! }
+//!
+//! fn main() {
+//!     let mut hasher1 = X33aU32::new();
+//!     let mut hasher2 = X33aU32::new();
+//!     Header { name: "ab".into(), value: "c".into() }.djb_hash(&mut hasher1);
+//!     Header { name: "a".into(), value: "bc".into() }.djb_hash(&mut hasher2);
+//!     assert_ne!(hasher1.finish(), hasher2.finish());
+//! }
+//! ```
+//!
+//! Keys that can't be separated by any salt are a compile error that names
+//! them:
+//!
+//! ```compile_fail
+//! #[macro_use]
+//! extern crate djb_hash_macros;
+//! extern crate djb_hash;
+//! use djb_hash::phf::PhfMap;
+//!
+//! // error: keys "Ez", "FY" collide for every salt
+//! static CLASH: PhfMap<u8> = djb_phf_map! { "Ez" => 1, "FY" => 2 };
+//!
+//! fn main() {}
+//! ```
+//!
+extern crate djb_hash;
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use djb_hash::phf::{PhfError, SaltTable};
+use proc_macro::TokenStream;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, Fields, LitStr, Token};
+
+///
+/// Number of salts tried for each table size.
+///
+const SALTS: u32 = 256;
+
+///
+/// Largest table size tried as a multiple of the number of keys.
+///
+const MAX_SIZE_FACTOR: usize = 4;
+
+struct Entry {
+    key: LitStr,
+    value: Expr,
+}
+
+impl Parse for Entry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let value = input.parse()?;
+        Ok(Entry { key, value })
+    }
+}
+
+struct Entries(Vec<Entry>);
+
+impl Parse for Entries {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let entries = Punctuated::<Entry, Token![,]>::parse_terminated(input)?;
+        Ok(Entries(entries.into_iter().collect()))
+    }
+}
+
+///
+/// Builds a `djb_hash::phf::PhfMap` from `"key" => value` pairs.
+///
+/// Table sizes from the number of keys up to four times that are tried with
+/// 256 salts each. If none of them gives every key its own slot the keys that
+/// still collided are reported as a compile error.
+///
+#[proc_macro]
+pub fn djb_phf_map(input: TokenStream) -> TokenStream {
+    let entries = syn::parse_macro_input!(input as Entries).0;
+    let keys: Vec<String> = entries.iter().map(|e| e.key.value()).collect();
+    let table = match SaltTable::search(&keys, SALTS, keys.len() * MAX_SIZE_FACTOR) {
+        Ok(table) => table,
+        Err(error) => return compile_error(&entries, &keys, &error),
+    };
+    let salt = table.salt;
+    let slots = table.slots.iter().map(|slot| match *slot {
+        Some(i) => {
+            let key = &entries[i].key;
+            let value = &entries[i].value;
+            quote!(::std::option::Option::Some((#key, #value)))
+        }
+        None => quote!(::std::option::Option::None),
+    });
+    let expanded = quote! {
+        ::djb_hash::phf::PhfMap {
+            salt: #salt,
+            entries: &[#(#slots),*],
+        }
+    };
+    expanded.into()
+}
+
+fn compile_error(entries: &[Entry], keys: &[String], error: &PhfError) -> TokenStream {
+    let first = match *error {
+        PhfError::DuplicateKey(ref key) => Some(key),
+        PhfError::Clash(ref clashing) | PhfError::NotFound(ref clashing) => clashing.first(),
+    };
+    let first = first.and_then(|key| keys.iter().rposition(|k| k.as_bytes() == &key[..]));
+    let span = match first {
+        Some(i) => entries[i].key.span(),
+        None => proc_macro2::Span::call_site(),
+    };
+    // syn::Error::to_compile_error() uses ::core which 2015 edition crates
+    // can't see so the error is built by hand.
+    let message = error.to_string();
+    let expanded = quote_spanned!(span=> compile_error!(#message));
+    expanded.into()
+}
+
+///
+/// Derives `djb_hash::DjbHash` for a struct or enum.
+///
+/// Struct fields are written in declaration order. Enums write the index of
+/// the variant as a `u32` first, then its fields. Every type parameter gets a
+/// `DjbHash` bound. Unions are not supported.
+///
+/// ```rust
+/// #[macro_use]
+/// extern crate djb_hash_macros;
+/// extern crate djb_hash;
+/// use std::hash::Hasher;
+/// use djb_hash::DjbHash;
+/// use djb_hash::x33a::X33a;
+///
+/// #[derive(DjbHash)]
+/// enum Shape<T> {
+///     Point,
+///     Circle(T),
+///     Rect { width: T, height: T },
+/// }
+///
+/// fn main() {
+///     let mut hasher1 = X33a::new();
+///     let mut hasher2 = X33a::new();
+///     Shape::Rect { width: 3u8, height: 4u8 }.djb_hash(&mut hasher1);
+///     hasher2.write(&[2, 0, 0, 0, 3, 4]);
+///     assert_eq!(hasher1.finish(), hasher2.finish());
+///     let mut hasher3 = X33a::new();
+///     Shape::Point::<u8>.djb_hash(&mut hasher3);
+///     assert_eq!(hasher3.finish(), X33a::new().finish() * 33u64.pow(4));
+/// }
+/// ```
+///
+#[proc_macro_derive(DjbHash)]
+pub fn derive_djb_hash(input: TokenStream) -> TokenStream {
+    let mut input = syn::parse_macro_input!(input as DeriveInput);
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(::djb_hash::DjbHash));
+    }
+    let name = &input.ident;
+    let arms = match input.data {
+        Data::Struct(ref data) => vec![arm(quote!(#name), &data.fields, None)],
+        Data::Enum(ref data) => data
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(index, variant)| {
+                let path = &variant.ident;
+                arm(quote!(#name::#path), &variant.fields, Some(index as u32))
+            })
+            .collect(),
+        Data::Union(ref data) => {
+            let message = "DjbHash can not be derived for unions";
+            let expanded = quote_spanned!(data.union_token.span()=> compile_error!(#message););
+            return expanded.into();
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expanded = quote! {
+        impl #impl_generics ::djb_hash::DjbHash for #name #ty_generics #where_clause {
+            fn djb_hash<__H: ::std::hash::Hasher>(&self, __state: &mut __H) {
+                match *self {
+                    #(#arms)*
+                }
+            }
+        }
+    };
+    expanded.into()
+}
+
+fn arm(
+    path: proc_macro2::TokenStream,
+    fields: &Fields,
+    tag: Option<u32>,
+) -> proc_macro2::TokenStream {
+    let bindings: Vec<_> = (0..fields.len())
+        .map(|i| proc_macro2::Ident::new(&format!("__self_{}", i), proc_macro2::Span::call_site()))
+        .collect();
+    let pattern = match *fields {
+        Fields::Named(ref named) => {
+            let names = named.named.iter().map(|f| &f.ident);
+            quote!(#path { #(#names: ref #bindings),* })
+        }
+        Fields::Unnamed(_) => quote!(#path(#(ref #bindings),*)),
+        Fields::Unit => quote!(#path),
+    };
+    let tag = tag.map(|tag| quote!(::djb_hash::DjbHash::djb_hash(&#tag, __state);));
+    quote! {
+        #pattern => {
+            #tag
+            #(::djb_hash::DjbHash::djb_hash(#bindings, __state);)*
+        }
+    }
+}
@@ -0,0 +1,376 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use apr::AprHash;
+use elf_hash::ElfHash;
+use g_str_hash::GStrHash;
+use hash_value::HashValue;
+use libiberty_hash::LibibertyHash;
+use lua_hash::{LuaHash, LuaVersion};
+use nginx::{NgxHashKey, NgxHashKeyLc};
+use perl_hash::PerlHash;
+use py2_str_hash::Py2StrHash;
+use py2_str_hash_u32::Py2StrHashU32;
+use redis::RedisCaseHash;
+use sdbm::Sdbm;
+#[cfg(feature = "serde")]
+use serde::de::{self, Deserialize, Deserializer, Unexpected};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::hash::Hasher;
+use tcl_hash::TclHash;
+use x33a::X33a;
+use x33a_php::X33aPhp;
+use x33a_php_signed::X33aPhpSigned;
+use x33a_signed::X33aSigned;
+use x33a_u32::X33aU32;
+use x33a_u32_php::X33aU32Php;
+use x33a_u32_php_signed::X33aU32PhpSigned;
+use x33a_u32_signed::X33aU32Signed;
+use x33x::X33x;
+use x33x_u32::X33xU32;
+
+///
+/// Names each of the hash functions in the crate so one can be picked at run
+/// time.
+///
+/// Each one also has a fixed id, its position in `Algorithm::ALL`, which is
+/// what checkpoints store. New hashes are only ever added to the end.
+///
+/// # Examples
+///
+/// ```rust
+/// use djb_hash::Algorithm;
+/// let algorithm = Algorithm::X33aU32Php;
+/// assert_eq!(algorithm.name(), "x33a_u32_php");
+/// assert_eq!(algorithm.bits(), 32);
+/// assert_eq!(algorithm.hash(5381, b"Ez"), 2153345956u64);
+/// assert_eq!(Algorithm::from_name("x33a_u32_php"), Some(algorithm));
+/// ```
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Algorithm {
+    X33a,
+    X33aPhp,
+    X33aU32,
+    X33aU32Php,
+    X33x,
+    X33xU32,
+    X33aSigned,
+    X33aPhpSigned,
+    X33aU32Signed,
+    X33aU32PhpSigned,
+    ElfHash,
+    GStrHash,
+    AprHash,
+    RedisCaseHash,
+    LuaHash,
+    NgxHashKey,
+    NgxHashKeyLc,
+    PerlHash,
+    Py2StrHash,
+    Py2StrHashU32,
+    Sdbm,
+    TclHash,
+    LibibertyHash,
+}
+
+impl Algorithm {
+    ///
+    /// The original DJB style add and XOR hashes in both widths along with
+    /// their PHP variants.
+    ///
+    pub const DJB: [Algorithm; 6] = [
+        Algorithm::X33a,
+        Algorithm::X33aPhp,
+        Algorithm::X33aU32,
+        Algorithm::X33aU32Php,
+        Algorithm::X33x,
+        Algorithm::X33xU32,
+    ];
+    ///
+    /// Every hash in the crate, in id order.
+    ///
+    pub const ALL: [Algorithm; 23] = [
+        Algorithm::X33a,
+        Algorithm::X33aPhp,
+        Algorithm::X33aU32,
+        Algorithm::X33aU32Php,
+        Algorithm::X33x,
+        Algorithm::X33xU32,
+        Algorithm::X33aSigned,
+        Algorithm::X33aPhpSigned,
+        Algorithm::X33aU32Signed,
+        Algorithm::X33aU32PhpSigned,
+        Algorithm::ElfHash,
+        Algorithm::GStrHash,
+        Algorithm::AprHash,
+        Algorithm::RedisCaseHash,
+        Algorithm::LuaHash,
+        Algorithm::NgxHashKey,
+        Algorithm::NgxHashKeyLc,
+        Algorithm::PerlHash,
+        Algorithm::Py2StrHash,
+        Algorithm::Py2StrHashU32,
+        Algorithm::Sdbm,
+        Algorithm::TclHash,
+        Algorithm::LibibertyHash,
+    ];
+    ///
+    /// Returns the snake case name of the hash.
+    ///
+    /// For the X33 family this is the name of the module it lives in, for the
+    /// others it is the name of the hasher type.
+    ///
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Algorithm::X33a => "x33a",
+            Algorithm::X33aPhp => "x33a_php",
+            Algorithm::X33aU32 => "x33a_u32",
+            Algorithm::X33aU32Php => "x33a_u32_php",
+            Algorithm::X33x => "x33x",
+            Algorithm::X33xU32 => "x33x_u32",
+            Algorithm::X33aSigned => "x33a_signed",
+            Algorithm::X33aPhpSigned => "x33a_php_signed",
+            Algorithm::X33aU32Signed => "x33a_u32_signed",
+            Algorithm::X33aU32PhpSigned => "x33a_u32_php_signed",
+            Algorithm::ElfHash => "elf_hash",
+            Algorithm::GStrHash => "g_str_hash",
+            Algorithm::AprHash => "apr_hash",
+            Algorithm::RedisCaseHash => "redis_case_hash",
+            Algorithm::LuaHash => "lua_hash",
+            Algorithm::NgxHashKey => "ngx_hash_key",
+            Algorithm::NgxHashKeyLc => "ngx_hash_key_lc",
+            Algorithm::PerlHash => "perl_hash",
+            Algorithm::Py2StrHash => "py2_str_hash",
+            Algorithm::Py2StrHashU32 => "py2_str_hash_u32",
+            Algorithm::Sdbm => "sdbm",
+            Algorithm::TclHash => "tcl_hash",
+            Algorithm::LibibertyHash => "libiberty_hash",
+        }
+    }
+    ///
+    /// Returns the hash with the given name().
+    ///
+    pub fn from_name(name: &str) -> Option<Algorithm> {
+        Algorithm::ALL.iter().cloned().find(|a| a.name() == name)
+    }
+    ///
+    /// Returns the fixed id of the hash.
+    ///
+    pub fn id(&self) -> u16 {
+        *self as u16
+    }
+    ///
+    /// Returns the hash with the given id().
+    ///
+    pub fn from_id(id: u16) -> Option<Algorithm> {
+        Algorithm::ALL.get(id as usize).cloned()
+    }
+    ///
+    /// Returns the number of bits in the internal hash total.
+    ///
+    pub fn bits(&self) -> u32 {
+        match *self {
+            Algorithm::X33a
+            | Algorithm::X33aPhp
+            | Algorithm::X33x
+            | Algorithm::X33aSigned
+            | Algorithm::X33aPhpSigned
+            | Algorithm::NgxHashKey
+            | Algorithm::NgxHashKeyLc
+            | Algorithm::Py2StrHash => 64,
+            _ => 32,
+        }
+    }
+    ///
+    /// Returns false for the hashes whose originals have no salt, which
+    /// hasher() ignores the salt for.
+    ///
+    /// These are `GStrHash`, `AprHash`, `NgxHashKey`, `NgxHashKeyLc`,
+    /// `PerlHash`, `TclHash` and `LibibertyHash`.
+    ///
+    pub fn takes_salt(&self) -> bool {
+        !matches!(
+            *self,
+            Algorithm::GStrHash
+                | Algorithm::AprHash
+                | Algorithm::NgxHashKey
+                | Algorithm::NgxHashKeyLc
+                | Algorithm::PerlHash
+                | Algorithm::TclHash
+                | Algorithm::LibibertyHash
+        )
+    }
+    ///
+    /// Returns the salt used by new().
+    ///
+    pub fn default_salt(&self) -> u64 {
+        match *self {
+            Algorithm::X33a
+            | Algorithm::X33aPhp
+            | Algorithm::X33aU32
+            | Algorithm::X33aU32Php
+            | Algorithm::X33x
+            | Algorithm::X33xU32
+            | Algorithm::X33aSigned
+            | Algorithm::X33aPhpSigned
+            | Algorithm::X33aU32Signed
+            | Algorithm::X33aU32PhpSigned
+            | Algorithm::GStrHash
+            | Algorithm::RedisCaseHash => 5381,
+            _ => 0,
+        }
+    }
+    ///
+    /// Creates a new boxed hasher using the given salt.
+    ///
+    /// For the 32 bit hashes only the lower 32 bits of the salt are used. The
+    /// salt is the seed for `LuaHash`, which is made for Lua 5.3, and the
+    /// prefix for the Python 2 hashes, with a suffix of 0.
+    ///
+    /// `GStrHash`, `AprHash`, `NgxHashKey`, `NgxHashKeyLc`, `PerlHash`,
+    /// `TclHash` and `LibibertyHash` have no salt, see takes_salt(), so for
+    /// them the salt is ignored and they start from their default_salt().
+    ///
+    pub fn hasher(&self, salt: u64) -> Box<dyn Hasher> {
+        match *self {
+            Algorithm::X33a => Box::new(X33a::new_with_salt(salt)),
+            Algorithm::X33aPhp => Box::new(X33aPhp::new_with_salt(salt)),
+            Algorithm::X33aU32 => Box::new(X33aU32::new_with_salt(salt as u32)),
+            Algorithm::X33aU32Php => Box::new(X33aU32Php::new_with_salt(salt as u32)),
+            Algorithm::X33x => Box::new(X33x::new_with_salt(salt)),
+            Algorithm::X33xU32 => Box::new(X33xU32::new_with_salt(salt as u32)),
+            Algorithm::X33aSigned => Box::new(X33aSigned::new_with_salt(salt)),
+            Algorithm::X33aPhpSigned => Box::new(X33aPhpSigned::new_with_salt(salt)),
+            Algorithm::X33aU32Signed => Box::new(X33aU32Signed::new_with_salt(salt as u32)),
+            Algorithm::X33aU32PhpSigned => Box::new(X33aU32PhpSigned::new_with_salt(salt as u32)),
+            Algorithm::ElfHash => Box::new(ElfHash::new_with_salt(salt as u32)),
+            Algorithm::GStrHash => Box::new(GStrHash::new()),
+            Algorithm::AprHash => Box::new(AprHash::new()),
+            Algorithm::RedisCaseHash => Box::new(RedisCaseHash::new_with_salt(salt as u32)),
+            Algorithm::LuaHash => Box::new(LuaHash::new_with_salt(LuaVersion::Lua53, salt as u32)),
+            Algorithm::NgxHashKey => Box::new(NgxHashKey::new()),
+            Algorithm::NgxHashKeyLc => Box::new(NgxHashKeyLc::new()),
+            Algorithm::PerlHash => Box::new(PerlHash::new()),
+            Algorithm::Py2StrHash => Box::new(Py2StrHash::new_with_secret(salt, 0)),
+            Algorithm::Py2StrHashU32 => Box::new(Py2StrHashU32::new_with_secret(salt as u32, 0)),
+            Algorithm::Sdbm => Box::new(Sdbm::new_with_salt(salt as u32)),
+            Algorithm::TclHash => Box::new(TclHash::new()),
+            Algorithm::LibibertyHash => Box::new(LibibertyHash::new()),
+        }
+    }
+    ///
+    /// Hashes the bytes in one go using the given salt.
+    ///
+    pub fn hash(&self, salt: u64, bytes: &[u8]) -> u64 {
+        let mut hasher = self.hasher(salt);
+        hasher.write(bytes);
+        hasher.finish()
+    }
+    ///
+    /// Hashes the bytes in one go using the given salt, tagging the value
+    /// with the algorithm and salt.
+    ///
+    pub fn hash_value(&self, salt: u64, bytes: &[u8]) -> HashValue {
+        HashValue::new(*self, salt, self.hash(salt, bytes))
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Algorithm {
+    ///
+    /// Serializes the hash as its name().
+    ///
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Algorithm {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Algorithm::from_name(&name)
+            .ok_or_else(|| de::Error::invalid_value(Unexpected::Str(&name), &"a hash name"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let input = "Ez";
+        assert_eq!(Algorithm::X33a.hash(5381, input.as_bytes()), 5862308u64);
+        assert_eq!(
+            Algorithm::X33aPhp.hash(5381, input.as_bytes()),
+            9223372036860638116u64
+        );
+        assert_eq!(Algorithm::X33aU32.hash(5381, input.as_bytes()), 5862308u64);
+        assert_eq!(
+            Algorithm::X33aU32Php.hash(5381, input.as_bytes()),
+            2153345956u64
+        );
+        assert_eq!(Algorithm::X33x.hash(5381, input.as_bytes()), 5861786u64);
+        assert_eq!(Algorithm::X33xU32.hash(5381, input.as_bytes()), 5861786u64);
+        assert_eq!(Algorithm::X33aU32Php.to_string(), "x33a_u32_php");
+        assert!(Algorithm::X33aU32Php.takes_salt());
+        assert_eq!(Algorithm::ElfHash.hash(0, input.as_bytes()), 1226u64);
+    }
+
+    #[test]
+    fn it_does_look_up_correctly() {
+        for (i, algorithm) in Algorithm::ALL.iter().enumerate() {
+            assert_eq!(algorithm.id() as usize, i);
+            assert_eq!(Algorithm::from_id(algorithm.id()), Some(*algorithm));
+            assert_eq!(Algorithm::from_name(algorithm.name()), Some(*algorithm));
+            let mut hasher = algorithm.hasher(algorithm.default_salt());
+            hasher.write(b"Ez");
+            assert_eq!(
+                hasher.finish(),
+                algorithm.hash(algorithm.default_salt(), b"Ez")
+            );
+        }
+        assert_eq!(Algorithm::from_id(23), None);
+        assert!(!Algorithm::TclHash.takes_salt());
+        assert_eq!(
+            Algorithm::TclHash.hash(7919, b"Ez"),
+            Algorithm::TclHash.hash(0, b"Ez")
+        );
+        assert_eq!(Algorithm::from_name("x33"), None);
+    }
+}
@@ -0,0 +1,576 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Hash quality analysis loosely modeled on the SMHasher test suite.
+//!
+//! The tests here are meant to give some real numbers to claims like "X33x
+//! distributes better than X33a" instead of having to take them on faith. They
+//! work with any `Hasher` by way of a factory closure that returns a fresh
+//! hasher for each key, so salted hashers can be compared just as easily as
+//! the defaults.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::analysis::*;
+//! use djb_hash::x33a_u32::*;
+//! use djb_hash::x33x_u32::*;
+//! let keys = ["GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS"];
+//! let analysis = Analysis::new(32).buckets(8);
+//! let x33a = analysis.run(X33aU32::new, &keys);
+//! let x33x = analysis.run(X33xU32::new, &keys);
+//! println!("{}", x33a);
+//! println!("{}", x33x.to_csv());
+//! ```
+//!
+use std::fmt;
+use std::hash::Hasher;
+
+///
+/// Settings used to run the analysis tests.
+///
+/// The defaults are kept small enough to run in well under a second even in
+/// debug builds. Increase the sample counts when a more precise answer is
+/// needed.
+///
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    bits: u32,
+    key_len: usize,
+    samples: usize,
+    sparse_bits: u32,
+    cycle_len: usize,
+    cycle_reps: usize,
+    sequential: usize,
+    buckets: usize,
+}
+
+impl Analysis {
+    ///
+    /// Creates a new analysis for a hash with the given output width in bits.
+    ///
+    /// The width should be 32 for the U32 hashes and 64 for the rest. Any
+    /// bits above the width returned by finish() are ignored.
+    ///
+    pub fn new(bits: u32) -> Self {
+        assert!(
+            bits > 0 && bits <= 64,
+            "Hash width must be between 1 and 64 bits"
+        );
+        Analysis {
+            bits,
+            key_len: 4,
+            samples: 256,
+            sparse_bits: 2,
+            cycle_len: 4,
+            cycle_reps: 8,
+            sequential: 10_000,
+            buckets: 1024,
+        }
+    }
+    ///
+    /// Sets the length in bytes of the keys used for the avalanche, bit
+    /// independence and sparse key tests.
+    ///
+    pub fn key_len(mut self, key_len: usize) -> Self {
+        assert!(key_len > 0, "Key length must be at least one byte");
+        self.key_len = key_len;
+        self
+    }
+    ///
+    /// Sets the number of random keys used by the avalanche, bit independence
+    /// and cyclic key tests.
+    ///
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+    ///
+    /// Sets the most bits that can be set in a single sparse key.
+    ///
+    pub fn sparse_bits(mut self, sparse_bits: u32) -> Self {
+        self.sparse_bits = sparse_bits;
+        self
+    }
+    ///
+    /// Sets the length of the repeated block and how many times it repeats in
+    /// the cyclic key test.
+    ///
+    pub fn cycles(mut self, cycle_len: usize, cycle_reps: usize) -> Self {
+        assert!(cycle_len > 0, "Cycle length must be at least one byte");
+        self.cycle_len = cycle_len;
+        self.cycle_reps = cycle_reps;
+        self
+    }
+    ///
+    /// Sets how many sequential decimal number keys, "0", "1", "2" etc, are
+    /// hashed.
+    ///
+    pub fn sequential(mut self, sequential: usize) -> Self {
+        self.sequential = sequential;
+        self
+    }
+    ///
+    /// Sets the number of buckets used for the chi-square test of the user
+    /// supplied keys.
+    ///
+    pub fn buckets(mut self, buckets: usize) -> Self {
+        assert!(buckets > 1, "Need at least two buckets");
+        self.buckets = buckets;
+        self
+    }
+    ///
+    /// Runs all of the tests and returns the combined report.
+    ///
+    /// The factory is called once for every key hashed so it must return a
+    /// fresh hasher each time. The user supplied keys are only used for the
+    /// bucket chi-square test and it is skipped when no keys are given.
+    ///
+    pub fn run<H, F, K>(&self, factory: F, keys: &[K]) -> Report
+    where
+        H: Hasher,
+        F: Fn() -> H,
+        K: AsRef<[u8]>,
+    {
+        let (avalanche, bit_independence) = self.avalanche(&factory);
+        Report {
+            bits: self.bits,
+            avalanche,
+            bit_independence,
+            sparse: self.sparse(&factory),
+            cyclic: self.cyclic(&factory),
+            sequential: self.sequential_keys(&factory),
+            buckets: if keys.is_empty() {
+                None
+            } else {
+                Some(self.chi_square(&factory, keys))
+            },
+        }
+    }
+    fn mask(&self) -> u64 {
+        if self.bits == 64 {
+            !0u64
+        } else {
+            (1u64 << self.bits) - 1
+        }
+    }
+    fn hash<H: Hasher, F: Fn() -> H>(&self, factory: &F, key: &[u8]) -> u64 {
+        let mut hasher = factory();
+        hasher.write(key);
+        hasher.finish() & self.mask()
+    }
+    fn avalanche<H: Hasher, F: Fn() -> H>(&self, factory: &F) -> (Avalanche, BitIndependence) {
+        let in_bits = self.key_len * 8;
+        let out_bits = self.bits as usize;
+        let mut flips = vec![vec![0usize; out_bits]; in_bits];
+        let mut pairs = vec![0usize; in_bits * out_bits * out_bits];
+        let mut rng = XorShift::new();
+        let mut key = vec![0u8; self.key_len];
+        for _ in 0..self.samples {
+            rng.fill(&mut key);
+            let base = self.hash(factory, &key);
+            for i in 0..in_bits {
+                key[i / 8] ^= 1 << (i % 8);
+                let diff = base ^ self.hash(factory, &key);
+                key[i / 8] ^= 1 << (i % 8);
+                for j in 0..out_bits {
+                    if diff >> j & 1 == 0 {
+                        continue;
+                    }
+                    flips[i][j] += 1;
+                    for k in j..out_bits {
+                        if diff >> k & 1 == 1 {
+                            pairs[(i * out_bits + j) * out_bits + k] += 1;
+                        }
+                    }
+                }
+            }
+        }
+        let n = self.samples.max(1) as f64;
+        let matrix: Vec<Vec<f64>> = flips
+            .iter()
+            .map(|row| row.iter().map(|&c| c as f64 / n).collect())
+            .collect();
+        let worst_bias = matrix
+            .iter()
+            .flat_map(|row| row.iter())
+            .map(|p| (p - 0.5).abs() * 2.0)
+            .fold(0.0, f64::max);
+        let mut worst_correlation = 0.0f64;
+        for (i, row) in flips.iter().enumerate() {
+            for j in 0..out_bits {
+                for k in (j + 1)..out_bits {
+                    let nj = row[j] as f64;
+                    let nk = row[k] as f64;
+                    let njk = pairs[(i * out_bits + j) * out_bits + k] as f64;
+                    let variance = nj * (n - nj) * nk * (n - nk);
+                    let correlation = if variance == 0.0 {
+                        1.0
+                    } else {
+                        ((n * njk - nj * nk) / variance.sqrt()).abs()
+                    };
+                    worst_correlation = worst_correlation.max(correlation);
+                }
+            }
+        }
+        (
+            Avalanche { matrix, worst_bias },
+            BitIndependence { worst_correlation },
+        )
+    }
+    fn sparse<H: Hasher, F: Fn() -> H>(&self, factory: &F) -> Collisions {
+        let in_bits = self.key_len * 8;
+        let mut hashes = Vec::new();
+        let mut key = vec![0u8; self.key_len];
+        hashes.push(self.hash(factory, &key));
+        sparse_keys(&mut key, 0, in_bits, self.sparse_bits, &mut |key| {
+            hashes.push(self.hash(factory, key));
+        });
+        Collisions::count(hashes, self.bits)
+    }
+    fn cyclic<H: Hasher, F: Fn() -> H>(&self, factory: &F) -> Collisions {
+        let mut rng = XorShift::new();
+        let mut cycle = vec![0u8; self.cycle_len];
+        let mut key = Vec::with_capacity(self.cycle_len * self.cycle_reps);
+        let hashes = (0..self.samples)
+            .map(|_| {
+                rng.fill(&mut cycle);
+                key.clear();
+                for _ in 0..self.cycle_reps {
+                    key.extend_from_slice(&cycle);
+                }
+                self.hash(factory, &key)
+            })
+            .collect();
+        Collisions::count(hashes, self.bits)
+    }
+    fn sequential_keys<H: Hasher, F: Fn() -> H>(&self, factory: &F) -> Collisions {
+        let hashes = (0..self.sequential)
+            .map(|i| self.hash(factory, i.to_string().as_bytes()))
+            .collect();
+        Collisions::count(hashes, self.bits)
+    }
+    fn chi_square<H: Hasher, F: Fn() -> H, K: AsRef<[u8]>>(
+        &self,
+        factory: &F,
+        keys: &[K],
+    ) -> BucketChiSquare {
+        let mut loads = vec![0usize; self.buckets];
+        for key in keys {
+            loads[(self.hash(factory, key.as_ref()) % self.buckets as u64) as usize] += 1;
+        }
+        let expected = keys.len() as f64 / self.buckets as f64;
+        let chi_square = loads
+            .iter()
+            .map(|&load| (load as f64 - expected).powi(2) / expected)
+            .sum();
+        BucketChiSquare {
+            keys: keys.len(),
+            buckets: self.buckets,
+            chi_square,
+            degrees_of_freedom: self.buckets - 1,
+            max_load: loads.iter().cloned().max().unwrap_or(0),
+        }
+    }
+}
+
+///
+/// Result of the avalanche test.
+///
+/// `matrix[i][j]` is the fraction of samples where flipping input bit `i`
+/// flipped output bit `j`. An ideal hash has every entry close to 0.5.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Avalanche {
+    pub matrix: Vec<Vec<f64>>,
+    ///
+    /// Largest distance of any matrix entry from 0.5, scaled so 0.0 is ideal
+    /// and 1.0 means some output bit always or never flips.
+    ///
+    pub worst_bias: f64,
+}
+
+impl Avalanche {
+    ///
+    /// Renders the full matrix as CSV with one row per input bit.
+    ///
+    pub fn matrix_csv(&self) -> String {
+        let mut out = String::from("input_bit");
+        if let Some(row) = self.matrix.first() {
+            for j in 0..row.len() {
+                out.push_str(&format!(",out_{}", j));
+            }
+        }
+        out.push('\n');
+        for (i, row) in self.matrix.iter().enumerate() {
+            out.push_str(&i.to_string());
+            for p in row {
+                out.push_str(&format!(",{:.4}", p));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+///
+/// Result of the bit independence test.
+///
+/// For each input bit flip the correlation between every pair of output bit
+/// flips is measured. An output bit that never or always flips counts as fully
+/// correlated since it carries no information at all.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitIndependence {
+    pub worst_correlation: f64,
+}
+
+///
+/// Collision count for one of the generated key sets.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Collisions {
+    pub keys: usize,
+    pub collisions: usize,
+    ///
+    /// Number of collisions a perfectly random hash of the same width would be
+    /// expected to have with the same number of keys.
+    ///
+    pub expected: f64,
+}
+
+impl Collisions {
+    fn count(mut hashes: Vec<u64>, bits: u32) -> Self {
+        let keys = hashes.len();
+        hashes.sort_unstable();
+        hashes.dedup();
+        let pairs = keys as f64 * (keys as f64 - 1.0) / 2.0;
+        Collisions {
+            keys,
+            collisions: keys - hashes.len(),
+            expected: pairs / 2f64.powi(bits as i32),
+        }
+    }
+}
+
+///
+/// Result of the bucket chi-square test over the user supplied keys.
+///
+/// A chi-square value close to the degrees of freedom means the keys are
+/// spread about as evenly as random chance would give.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketChiSquare {
+    pub keys: usize,
+    pub buckets: usize,
+    pub chi_square: f64,
+    pub degrees_of_freedom: usize,
+    pub max_load: usize,
+}
+
+///
+/// Combined results of all the tests.
+///
+/// The Display implementation gives a short human readable summary and
+/// to_csv() gives the same numbers in a form easy to load in a spreadsheet or
+/// compare between runs.
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub bits: u32,
+    pub avalanche: Avalanche,
+    pub bit_independence: BitIndependence,
+    pub sparse: Collisions,
+    pub cyclic: Collisions,
+    pub sequential: Collisions,
+    pub buckets: Option<BucketChiSquare>,
+}
+
+impl Report {
+    ///
+    /// Renders the report as CSV with `test,metric,value` rows.
+    ///
+    pub fn to_csv(&self) -> String {
+        let mut rows = vec![
+            ("hash", "bits", self.bits.to_string()),
+            (
+                "avalanche",
+                "worst_bias",
+                format!("{:.6}", self.avalanche.worst_bias),
+            ),
+            (
+                "bit_independence",
+                "worst_correlation",
+                format!("{:.6}", self.bit_independence.worst_correlation),
+            ),
+        ];
+        for &(name, c) in &[
+            ("sparse", &self.sparse),
+            ("cyclic", &self.cyclic),
+            ("sequential", &self.sequential),
+        ] {
+            rows.push((name, "keys", c.keys.to_string()));
+            rows.push((name, "collisions", c.collisions.to_string()));
+            rows.push((name, "expected", format!("{:.6}", c.expected)));
+        }
+        if let Some(ref b) = self.buckets {
+            rows.push(("buckets", "keys", b.keys.to_string()));
+            rows.push(("buckets", "buckets", b.buckets.to_string()));
+            rows.push(("buckets", "chi_square", format!("{:.6}", b.chi_square)));
+            rows.push((
+                "buckets",
+                "degrees_of_freedom",
+                b.degrees_of_freedom.to_string(),
+            ));
+            rows.push(("buckets", "max_load", b.max_load.to_string()));
+        }
+        let mut out = String::from("test,metric,value\n");
+        for (test, metric, value) in rows {
+            out.push_str(&format!("{},{},{}\n", test, metric, value));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Hash quality report ({} bit)", self.bits)?;
+        writeln!(
+            f,
+            "  Avalanche:        worst bias {:.4} over {} input bits",
+            self.avalanche.worst_bias,
+            self.avalanche.matrix.len()
+        )?;
+        writeln!(
+            f,
+            "  Bit independence: worst correlation {:.4}",
+            self.bit_independence.worst_correlation
+        )?;
+        for &(name, c) in &[
+            ("Sparse keys:     ", &self.sparse),
+            ("Cyclic keys:     ", &self.cyclic),
+            ("Sequential keys: ", &self.sequential),
+        ] {
+            writeln!(
+                f,
+                "  {} {} keys, {} collisions ({:.4} expected)",
+                name, c.keys, c.collisions, c.expected
+            )?;
+        }
+        if let Some(ref b) = self.buckets {
+            writeln!(
+                f,
+                "  Buckets:          {} keys in {} buckets, chi-square {:.2} ({} dof), max load {}",
+                b.keys, b.buckets, b.chi_square, b.degrees_of_freedom, b.max_load
+            )?;
+        }
+        Ok(())
+    }
+}
+
+///
+/// Calls back with every key made by setting up to `remaining` more bits at or
+/// above bit `from`.
+///
+fn sparse_keys<C: FnMut(&[u8])>(
+    key: &mut [u8],
+    from: usize,
+    bits: usize,
+    remaining: u32,
+    callback: &mut C,
+) {
+    if remaining == 0 {
+        return;
+    }
+    for i in from..bits {
+        key[i / 8] ^= 1 << (i % 8);
+        callback(key);
+        sparse_keys(key, i + 1, bits, remaining - 1, callback);
+        key[i / 8] ^= 1 << (i % 8);
+    }
+}
+
+///
+/// Small fixed seed xorshift generator so reports are repeatable between runs.
+///
+struct XorShift(u64);
+
+impl XorShift {
+    fn new() -> Self {
+        XorShift(0x2545_f491_4f6c_dd1d)
+    }
+    fn fill(&mut self, bytes: &mut [u8]) {
+        for byte in bytes {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            *byte = (self.0 >> 24) as u8;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x33a_u32::X33aU32;
+    use x33x::X33x;
+
+    #[test]
+    fn it_does_analyze_correctly() {
+        let keys = ["Ez", "FY", "abc", "abd"];
+        let sut = Analysis::new(32).samples(16).sequential(100).buckets(4);
+        let report = sut.run(X33aU32::new, &keys);
+        assert_eq!(report.avalanche.matrix.len(), 32);
+        assert_eq!(report.avalanche.matrix[0].len(), 32);
+        // Flipping the lowest bit of the last byte moves an add hash by one,
+        // so the lowest output bit always flips. Higher bits only flip when
+        // that carries or borrows into them, which never reaches the top bit
+        // for these samples.
+        assert_eq!(report.avalanche.matrix[24][0], 1.0);
+        assert_eq!(report.avalanche.matrix[24][31], 0.0);
+        assert_eq!(report.avalanche.worst_bias, 1.0);
+        // 1 all-zero key, 32 single bit keys and 496 two bit keys.
+        assert_eq!(report.sparse.keys, 529);
+        assert_eq!(report.sequential.keys, 100);
+        assert_eq!(report.sequential.collisions, 0);
+        let buckets = report.buckets.clone().unwrap();
+        assert_eq!(buckets.keys, 4);
+        // "Ez" and "FY" collide so share a bucket.
+        assert!(buckets.max_load >= 2);
+        assert!(report
+            .to_csv()
+            .starts_with("test,metric,value\nhash,bits,32\n"));
+        assert!(report
+            .to_string()
+            .starts_with("Hash quality report (32 bit)"));
+        let report = Analysis::new(64).samples(16).run(X33x::new, &[] as &[&str]);
+        assert!(report.buckets.is_none());
+        assert_eq!(report.avalanche.matrix[0].len(), 64);
+    }
+}
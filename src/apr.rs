@@ -0,0 +1,247 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Emulation of `apr_hashfunc_default()` from the Apache Portable Runtime.
+//!
+//! APR's default hash is the 32 bit times 33 hash over unsigned bytes
+//! starting from 0 instead of 5381, which is X33aU32 with a salt of 0. Keys
+//! can either be given with a length or as `APR_HASH_KEY_STRING`, in which
+//! case the key ends at the first NUL byte and APR hands back the length it
+//! found.
+//!
+//! The vectors in the tests were worked out from `apr_hash.c` in APR 1.x.
+//! Note that APR 1.6 and later seed the hash of their own tables with a
+//! random value, so these only match `apr_hashfunc_default()` itself or
+//! tables made with `apr_hash_make_custom()` using it, as Apache modules that
+//! need stable hashes do.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::apr;
+//! assert_eq!(apr::hash(b"mod_rewrite"), 4056549505);
+//! assert_eq!(apr::hash_key_string(b"abc\0def"), (108966, 3));
+//! ```
+//!
+use checkpoint::{flag, Checkpoint, CheckpointError, Resumable};
+use int_encoding::WriteIdentity;
+use std::hash::Hasher;
+use x33a_u32::X33aU32;
+use Algorithm;
+use HasherU32;
+use IntEncoding;
+
+///
+/// The starting value APR uses.
+///
+pub const APR_HASH_SEED: u32 = 0;
+
+///
+/// Returns the APR hash of a key with a known length.
+///
+pub fn hash(key: &[u8]) -> u32 {
+    let mut hasher = AprHash::new();
+    hasher.write(key);
+    hasher.finish_u32()
+}
+
+///
+/// Returns the APR hash of a key in `APR_HASH_KEY_STRING` mode along with
+/// the length of the key up to the first NUL byte.
+///
+pub fn hash_key_string(key: &[u8]) -> (u32, usize) {
+    let len = key.iter().position(|&b| b == 0).unwrap_or(key.len());
+    (hash(&key[..len]), len)
+}
+
+///
+/// Implements `apr_hashfunc_default()` as a hasher.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::apr::*;
+/// let mut hasher = AprHash::new();
+/// hasher.write(b"abc\0def");
+/// assert_eq!(hasher.finish_u32(), 376161141u32);
+/// let mut hasher = AprHash::new_key_string();
+/// hasher.write(b"abc\0def");
+/// assert_eq!(hasher.finish_u32(), 108966u32);
+/// ```
+///
+pub struct AprHash {
+    hasher: X33aU32,
+    key_string: bool,
+    ended: bool,
+    consumed: u64,
+    int_encoding: IntEncoding,
+}
+
+impl AprHash {
+    ///
+    /// Creates a new hash for keys with a known length.
+    ///
+    pub fn new() -> Self {
+        AprHash {
+            hasher: X33aU32::new_with_salt(APR_HASH_SEED),
+            key_string: false,
+            ended: false,
+            consumed: 0,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Creates a new hash for `APR_HASH_KEY_STRING` keys.
+    ///
+    /// The first NUL byte ends the key and every byte after it, in the same or
+    /// later writes, is ignored.
+    ///
+    pub fn new_key_string() -> Self {
+        AprHash {
+            key_string: true,
+            ..AprHash::new()
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for AprHash {
+    ///
+    /// Same as new(), for keys with a known length.
+    ///
+    fn default() -> Self {
+        AprHash::new()
+    }
+}
+
+impl HasherU32 for AprHash {
+    fn finish_u32(&self) -> u32 {
+        self.hasher.finish_u32()
+    }
+}
+
+impl WriteIdentity for AprHash {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        if !self.ended {
+            self.hasher.write_identity(value);
+        }
+    }
+}
+
+impl Hasher for AprHash {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        if self.ended {
+            return;
+        }
+        if !self.key_string {
+            self.hasher.write(bytes);
+            return;
+        }
+        match bytes.iter().position(|&b| b == 0) {
+            Some(end) => {
+                self.hasher.write(&bytes[..end]);
+                self.ended = true;
+            }
+            None => self.hasher.write(bytes),
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for AprHash {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            algorithm: Algorithm::AprHash,
+            consumed: self.consumed,
+            int_encoding: self.int_encoding,
+            extra: vec![self.key_string as u8, self.ended as u8],
+            ..self.hasher.checkpoint()
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        let extra = checkpoint.expect(Algorithm::AprHash, 2)?;
+        let inner = Checkpoint::new(
+            Algorithm::X33aU32,
+            checkpoint.salt,
+            checkpoint.state,
+            checkpoint.consumed,
+            IntEncoding::LittleEndian,
+        );
+        Ok(AprHash {
+            hasher: X33aU32::restore(&inner)?,
+            key_string: flag(extra[0])?,
+            ended: flag(extra[1])?,
+            consumed: checkpoint.consumed,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let vectors: [(&[u8], u32); 6] = [
+            (b"", 0),
+            (b"a", 97),
+            (b"foo", 114_852),
+            (b"key:1000", 1_018_389_668),
+            (b"Hello World", 2_618_181_788),
+            (b"caf\xc3\xa9", 121_009_750),
+        ];
+        for &(key, expected) in &vectors {
+            assert_eq!(hash(key), expected);
+            assert_eq!(hash_key_string(key), (expected, key.len()));
+        }
+        let mut sut = AprHash::new_key_string();
+        sut.write(b"ab");
+        sut.write(b"c\0d");
+        sut.write(b"ef");
+        assert_eq!(sut.finish(), 108_966);
+        assert_eq!(hash(b"abc\0def"), 376_161_141);
+    }
+}
@@ -0,0 +1,453 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! The hash functions of Berkeley DB and a reader for the meta data of its
+//! hash access method files.
+//!
+//! Berkeley DB has a small family of built in hash functions that
+//! `h_hash` can be set to, each of them 32 bits:
+//!
+//! * `BerkeleyDbHash::Ejb`, `hash1()` from ejb's hsearch, only in DB 1.85.
+//! * `BerkeleyDbHash::Phong`, Phong Vo's linear congruential hash.
+//! * `BerkeleyDbHash::Sdbm`, Ozan Yigit's sdbm hash, also found as the
+//!   `Sdbm` hasher.
+//! * `BerkeleyDbHash::Torek`, Chris Torek's times 33 hash, which is X33aU32
+//!   starting from 0 and the default of DB 1.85.
+//! * `BerkeleyDbHash::Fnv`, FNV-1 but starting from 0 instead of the FNV
+//!   offset basis, the default from DB 2.x on.
+//!
+//! Every hash file stores the hash of the string `"%$sniglet^&"`, including
+//! its NUL, so the hash the file was made with can be found again. The bucket
+//! of a key is its hash masked by `high_mask`, or by `low_mask` when that is
+//! past the last bucket in use, the same as `__call_hash()` in DB 1.85 and
+//! `__ham_call_hash()` in later versions.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::berkeley_db::*;
+//! let bytes = include_bytes!("../tests/fixtures/berkeley_db/keys.db");
+//! let meta = HashMeta::parse(&bytes[..]).unwrap();
+//! assert_eq!(meta.format, DbFormat::Db3);
+//! assert_eq!(meta.detect_hash(), Some(BerkeleyDbHash::Fnv));
+//! let bucket = meta.bucket(BerkeleyDbHash::Fnv.hash(b"key001"));
+//! assert!(bucket <= meta.max_bucket);
+//! ```
+//!
+use elf::Endian;
+use sdbm::Sdbm;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::{self, Read};
+use std::path::Path;
+use x33a_u32::X33aU32;
+use HasherU32;
+
+///
+/// The key every hash file stores the hash of, with its NUL.
+///
+pub const CHARKEY: &[u8] = b"%$sniglet^&\0";
+
+///
+/// The magic number of hash files.
+///
+pub const HASHMAGIC: u32 = 0x0006_1561;
+
+///
+/// The number of spare page counts kept in the meta data.
+///
+pub const NCACHED: usize = 32;
+
+///
+/// The built in hash functions of Berkeley DB.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BerkeleyDbHash {
+    Ejb,
+    Phong,
+    Sdbm,
+    Torek,
+    Fnv,
+}
+
+impl BerkeleyDbHash {
+    ///
+    /// Every hash function, oldest first.
+    ///
+    pub const ALL: [BerkeleyDbHash; 5] = [
+        BerkeleyDbHash::Ejb,
+        BerkeleyDbHash::Phong,
+        BerkeleyDbHash::Sdbm,
+        BerkeleyDbHash::Torek,
+        BerkeleyDbHash::Fnv,
+    ];
+    ///
+    /// Returns the name of the function in the DB sources.
+    ///
+    pub fn name(self) -> &'static str {
+        match self {
+            BerkeleyDbHash::Ejb => "hash1",
+            BerkeleyDbHash::Phong => "__ham_func2",
+            BerkeleyDbHash::Sdbm => "__ham_func3",
+            BerkeleyDbHash::Torek => "__ham_func4",
+            BerkeleyDbHash::Fnv => "__ham_func5",
+        }
+    }
+    ///
+    /// Returns the hash of the key.
+    ///
+    pub fn hash(self, key: &[u8]) -> u32 {
+        match self {
+            BerkeleyDbHash::Ejb => {
+                let mut hash: u32 = 0;
+                for &byte in key {
+                    hash = hash.wrapping_mul(37) ^ (byte as u32).wrapping_sub(b' ' as u32);
+                }
+                hash % 1_048_583
+            }
+            BerkeleyDbHash::Phong => {
+                let mut hash: u32 = 0;
+                for &byte in key {
+                    hash = hash
+                        .wrapping_mul(0x63c6_3cd9)
+                        .wrapping_add(0x9c39_c33d)
+                        .wrapping_add(byte as u32);
+                }
+                hash
+            }
+            BerkeleyDbHash::Sdbm => {
+                let mut hasher = Sdbm::new();
+                hasher.write(key);
+                hasher.finish_u32()
+            }
+            BerkeleyDbHash::Torek => {
+                let mut hasher = X33aU32::new_with_salt(0);
+                hasher.write(key);
+                hasher.finish_u32()
+            }
+            BerkeleyDbHash::Fnv => {
+                let mut hash: u32 = 0;
+                for &byte in key {
+                    hash = hash.wrapping_mul(16_777_619) ^ byte as u32;
+                }
+                hash
+            }
+        }
+    }
+    ///
+    /// Returns the default hash function of a file format.
+    ///
+    pub fn default_for(format: DbFormat) -> Self {
+        match format {
+            DbFormat::Db185 => BerkeleyDbHash::Torek,
+            DbFormat::Db2 | DbFormat::Db3 => BerkeleyDbHash::Fnv,
+        }
+    }
+}
+
+///
+/// The layouts of the hash meta data.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DbFormat {
+    ///
+    /// DB 1.85 and 1.86, also used by the BSD libc `dbopen()`.
+    ///
+    Db185,
+    ///
+    /// DB 2.x, hash versions 4 and 5.
+    ///
+    Db2,
+    ///
+    /// DB 3.0 and later, hash version 6 on.
+    ///
+    Db3,
+}
+
+///
+/// The hash meta data from the start of a hash file.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HashMeta {
+    pub format: DbFormat,
+    pub endian: Endian,
+    pub version: u32,
+    pub page_size: u32,
+    pub max_bucket: u32,
+    pub high_mask: u32,
+    pub low_mask: u32,
+    pub ffactor: u32,
+    pub nkeys: u32,
+    pub h_charkey: u32,
+    ///
+    /// The pages used by the header, always 1 after DB 1.85.
+    ///
+    pub hdrpages: u32,
+    pub spares: [u32; NCACHED],
+}
+
+impl HashMeta {
+    ///
+    /// Reads the meta data from the start of a file.
+    ///
+    /// Both byte orders are supported, found by where the magic number reads
+    /// right.
+    ///
+    pub fn parse(data: &[u8]) -> io::Result<Self> {
+        let endian = |pos| {
+            [Endian::Little, Endian::Big]
+                .iter()
+                .cloned()
+                .find(|e| e.read_u32(data, pos).ok() == Some(HASHMAGIC))
+        };
+        if let Some(endian) = endian(0) {
+            let version = read(data, endian, 4)?;
+            if version <= 3 {
+                return HashMeta::parse_185(data, endian, version);
+            }
+        }
+        let endian = endian(12).ok_or_else(|| invalid("not a Berkeley DB hash file"))?;
+        let version = read(data, endian, 16)?;
+        let (format, start, spares) = match version {
+            0..=3 => return Err(invalid("unknown Berkeley DB hash version")),
+            4 | 5 => (DbFormat::Db2, 32, 60),
+            _ => (DbFormat::Db3, 72, 96),
+        };
+        let mut meta = HashMeta {
+            format,
+            endian,
+            version,
+            page_size: read(data, endian, 20)?,
+            max_bucket: read(data, endian, start)?,
+            high_mask: read(data, endian, start + 4)?,
+            low_mask: read(data, endian, start + 8)?,
+            ffactor: read(data, endian, start + 12)?,
+            nkeys: read(data, endian, start + 16)?,
+            h_charkey: read(data, endian, start + 20)?,
+            hdrpages: 1,
+            spares: [0; NCACHED],
+        };
+        for (i, spare) in meta.spares.iter_mut().enumerate() {
+            *spare = read(data, endian, spares + i * 4)?;
+        }
+        Ok(meta)
+    }
+    fn parse_185(data: &[u8], endian: Endian, version: u32) -> io::Result<Self> {
+        let mut meta = HashMeta {
+            format: DbFormat::Db185,
+            endian,
+            version,
+            page_size: read(data, endian, 12)?,
+            max_bucket: read(data, endian, 40)?,
+            high_mask: read(data, endian, 44)?,
+            low_mask: read(data, endian, 48)?,
+            ffactor: read(data, endian, 52)?,
+            nkeys: read(data, endian, 56)?,
+            h_charkey: read(data, endian, 64)?,
+            hdrpages: read(data, endian, 60)?,
+            spares: [0; NCACHED],
+        };
+        for (i, spare) in meta.spares.iter_mut().enumerate() {
+            *spare = read(data, endian, 68 + i * 4)?;
+        }
+        Ok(meta)
+    }
+    ///
+    /// Reads the meta data from the start of a file.
+    ///
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut data = Vec::with_capacity(512);
+        File::open(path)?.take(512).read_to_end(&mut data)?;
+        HashMeta::parse(&data)
+    }
+    ///
+    /// Returns the built in hash function that gives the stored hash of
+    /// `CHARKEY`, trying the default of the format first.
+    ///
+    /// None means the file was made with a hash function of its own.
+    ///
+    pub fn detect_hash(&self) -> Option<BerkeleyDbHash> {
+        let default = BerkeleyDbHash::default_for(self.format);
+        Some(default)
+            .into_iter()
+            .chain(BerkeleyDbHash::ALL.iter().cloned())
+            .filter(|&h| h != BerkeleyDbHash::Ejb || self.format == DbFormat::Db185)
+            .find(|h| h.hash(CHARKEY) == self.h_charkey)
+    }
+    ///
+    /// Returns the bucket of a hash.
+    ///
+    pub fn bucket(&self, hash: u32) -> u32 {
+        let bucket = hash & self.high_mask;
+        if bucket > self.max_bucket {
+            bucket & self.low_mask
+        } else {
+            bucket
+        }
+    }
+    ///
+    /// Returns the page the bucket starts on, counting pages of `page_size`
+    /// from the start of the file.
+    ///
+    pub fn bucket_page(&self, bucket: u32) -> u32 {
+        match self.format {
+            DbFormat::Db185 | DbFormat::Db2 => {
+                let spare = if bucket == 0 {
+                    0
+                } else {
+                    self.spare(ceil_log2(bucket + 1) - 1)
+                };
+                bucket + self.hdrpages + spare
+            }
+            DbFormat::Db3 => bucket + self.spare(ceil_log2(bucket + 1)),
+        }
+    }
+    fn spare(&self, index: u32) -> u32 {
+        self.spares.get(index as usize).cloned().unwrap_or(0)
+    }
+}
+
+fn ceil_log2(num: u32) -> u32 {
+    match num {
+        0 | 1 => 0,
+        n => 32 - (n - 1).leading_zeros(),
+    }
+}
+
+fn read(data: &[u8], endian: Endian, pos: usize) -> io::Result<u32> {
+    endian
+        .read_u32(data, pos)
+        .map_err(|_| invalid("Berkeley DB hash meta data is truncated"))
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: &[u8] = include_bytes!("../tests/fixtures/berkeley_db/keys.db");
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let vectors: [(&[u8], [u32; 5]); 4] = [
+            (b"", [0, 0, 0, 0, 0]),
+            (
+                b"hello",
+                [
+                    984_995,
+                    3_464_949_309,
+                    684_824_882,
+                    127_086_708,
+                    3_966_594_024,
+                ],
+            ),
+            (
+                b"key001",
+                [561_852, 377_270_816, 643_415_858, 16_700_058, 1_522_669_638],
+            ),
+            (
+                CHARKEY,
+                [
+                    236_234,
+                    978_878_503,
+                    696_955_637,
+                    2_507_046_371,
+                    1_583_910_353,
+                ],
+            ),
+        ];
+        for &(key, ref expected) in &vectors {
+            for (hash, &value) in BerkeleyDbHash::ALL.iter().zip(expected) {
+                assert_eq!(hash.hash(key), value, "{}", hash.name());
+            }
+        }
+    }
+
+    #[test]
+    fn it_does_find_buckets_correctly() {
+        let sut = HashMeta::parse(KEYS).unwrap();
+        assert_eq!(sut.format, DbFormat::Db3);
+        assert_eq!(sut.endian, Endian::Little);
+        assert_eq!(sut.version, 9);
+        assert_eq!(sut.page_size, 512);
+        assert_eq!(sut.nkeys, 60);
+        assert_eq!((sut.max_bucket, sut.high_mask, sut.low_mask), (7, 7, 3));
+        assert_eq!(sut.detect_hash(), Some(BerkeleyDbHash::Fnv));
+        for i in 1..61 {
+            let key = format!("key{:03}", i);
+            let bucket = sut.bucket(BerkeleyDbHash::Fnv.hash(key.as_bytes()));
+            let start = (sut.bucket_page(bucket) * sut.page_size) as usize;
+            let page = &KEYS[start..start + sut.page_size as usize];
+            assert!(page.windows(6).any(|w| w == key.as_bytes()), "{}", key);
+        }
+        // A DB 1.85 header in big endian order made with the default hash.
+        let mut header = Vec::new();
+        let fields = [
+            HASHMAGIC,
+            2,
+            4321,
+            256,
+            8,
+            256,
+            256,
+            8,
+            2,
+            0,
+            5,
+            7,
+            3,
+            8,
+            40,
+            1,
+            2_507_046_371,
+            0,
+            2,
+            3,
+        ];
+        for field in fields.iter() {
+            header.extend_from_slice(&field.to_be_bytes());
+        }
+        header.resize(260, 0);
+        let sut = HashMeta::parse(&header).unwrap();
+        assert_eq!(sut.format, DbFormat::Db185);
+        assert_eq!(sut.endian, Endian::Big);
+        assert_eq!(sut.detect_hash(), Some(BerkeleyDbHash::Torek));
+        assert_eq!(sut.bucket(6), 6 & 3);
+        assert_eq!(sut.bucket(5), 5);
+        assert_eq!(sut.bucket_page(0), 1);
+        assert_eq!(sut.bucket_page(1), 2);
+        assert_eq!(sut.bucket_page(2), 5);
+        assert_eq!(sut.bucket_page(4), 8);
+        assert!(HashMeta::parse(&header[..40]).is_err());
+        assert!(HashMeta::parse(b"not a database").is_err());
+    }
+}
@@ -0,0 +1,493 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Reader and writer for Daniel J. Bernstein's cdb constant database format.
+//!
+//! cdb hashes its keys with X33xU32, starting from the usual 5381. The writer
+//! lays out the file exactly like `cdbmake` does so the files are byte for
+//! byte the same, and the `+klen,dlen:key->data` text format used by
+//! `cdbmake` and `cdbdump` can be read and written to swap databases with
+//! tools like tinydns and qmail.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::io::Cursor;
+//! use djb_hash::cdb::*;
+//! let mut writer = CdbWriter::new(Cursor::new(Vec::new())).unwrap();
+//! writer.add(b"one", b"Hello").unwrap();
+//! writer.add(b"two", b"Goodbye").unwrap();
+//! writer.add(b"one", b", World!").unwrap();
+//! let bytes = writer.finish().unwrap().into_inner();
+//! let cdb = Cdb::new(bytes).unwrap();
+//! assert_eq!(cdb.get(b"two").unwrap(), Some(&b"Goodbye"[..]));
+//! let all: Vec<&[u8]> = cdb.get_all(b"one").map(|d| d.unwrap()).collect();
+//! assert_eq!(all, vec![&b"Hello"[..], &b", World!"[..]]);
+//! ```
+//!
+use std::fs;
+use std::hash::Hasher;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use x33x_u32::X33xU32;
+use HasherU32;
+
+///
+/// Size of the table of hash table positions at the start of every file.
+///
+const HEADER_SIZE: u32 = 2048;
+
+///
+/// Returns the cdb hash of the key.
+///
+pub fn hash(key: &[u8]) -> u32 {
+    let mut hasher = X33xU32::new();
+    hasher.write(key);
+    hasher.finish_u32()
+}
+
+///
+/// Streaming cdb writer.
+///
+/// Records are written out as they are added and only their hash and position
+/// are kept in memory. The hash tables and header are written by finish(),
+/// which is why the writer needs to be able to seek back to the start.
+///
+pub struct CdbWriter<W: Write + Seek> {
+    out: W,
+    pos: u32,
+    entries: Vec<(u32, u32)>,
+}
+
+impl<W: Write + Seek> CdbWriter<W> {
+    ///
+    /// Creates a new writer and reserves room for the header.
+    ///
+    pub fn new(mut out: W) -> io::Result<Self> {
+        out.seek(SeekFrom::Start(0))?;
+        out.write_all(&[0u8; HEADER_SIZE as usize])?;
+        Ok(CdbWriter {
+            out,
+            pos: HEADER_SIZE,
+            entries: Vec::new(),
+        })
+    }
+    ///
+    /// Adds a record. The same key can be added more than once.
+    ///
+    pub fn add(&mut self, key: &[u8], data: &[u8]) -> io::Result<()> {
+        let klen = to_u32(key.len())?;
+        let dlen = to_u32(data.len())?;
+        let pos = self.pos;
+        self.advance(8)?;
+        self.advance(klen)?;
+        self.advance(dlen)?;
+        self.out.write_all(&klen.to_le_bytes())?;
+        self.out.write_all(&dlen.to_le_bytes())?;
+        self.out.write_all(key)?;
+        self.out.write_all(data)?;
+        self.entries.push((hash(key), pos));
+        Ok(())
+    }
+    ///
+    /// Adds every record from `cdbmake` style text.
+    ///
+    /// Each record is `+klen,dlen:key->data` followed by a new line and the
+    /// input ends with an empty line.
+    ///
+    pub fn add_text<R: Read>(&mut self, input: R) -> io::Result<()> {
+        let mut input = BufReader::new(input);
+        let mut key = Vec::new();
+        let mut data = Vec::new();
+        loop {
+            match read_byte(&mut input)? {
+                b'\n' => return Ok(()),
+                b'+' => {}
+                _ => return Err(bad_text()),
+            }
+            let klen = read_number(&mut input, b',')?;
+            let dlen = read_number(&mut input, b':')?;
+            key.resize(klen, 0);
+            input.read_exact(&mut key)?;
+            expect(&mut input, b"->")?;
+            data.resize(dlen, 0);
+            input.read_exact(&mut data)?;
+            expect(&mut input, b"\n")?;
+            self.add(&key, &data)?;
+        }
+    }
+    ///
+    /// Writes the hash tables and header and returns the inner writer.
+    ///
+    pub fn finish(mut self) -> io::Result<W> {
+        let mut counts = [0usize; 256];
+        for &(h, _) in &self.entries {
+            counts[(h & 255) as usize] += 1;
+        }
+        let mut buckets: Vec<Vec<(u32, u32)>> =
+            counts.iter().map(|&c| Vec::with_capacity(c)).collect();
+        for &entry in &self.entries {
+            buckets[(entry.0 & 255) as usize].push(entry);
+        }
+        let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+        let mut table = Vec::new();
+        for bucket in &buckets {
+            let len = bucket.len() * 2;
+            header.extend_from_slice(&self.pos.to_le_bytes());
+            header.extend_from_slice(&to_u32(len)?.to_le_bytes());
+            table.clear();
+            table.resize(len, (0u32, 0u32));
+            for &(h, pos) in bucket {
+                let mut slot = (h >> 8) as usize % len;
+                while table[slot].1 != 0 {
+                    slot = (slot + 1) % len;
+                }
+                table[slot] = (h, pos);
+            }
+            for &(h, pos) in &table {
+                self.advance(8)?;
+                self.out.write_all(&h.to_le_bytes())?;
+                self.out.write_all(&pos.to_le_bytes())?;
+            }
+        }
+        self.out.seek(SeekFrom::Start(0))?;
+        self.out.write_all(&header)?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+    fn advance(&mut self, len: u32) -> io::Result<()> {
+        self.pos = self.pos.checked_add(len).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "cdb file would be over 4GB")
+        })?;
+        Ok(())
+    }
+}
+
+///
+/// Reader for a cdb file held in memory.
+///
+/// Any corruption found while reading is returned as an `InvalidData` error
+/// instead of causing a panic.
+///
+#[derive(Clone, Debug)]
+pub struct Cdb<T: AsRef<[u8]>> {
+    data: T,
+}
+
+impl Cdb<Vec<u8>> {
+    ///
+    /// Reads the whole file into memory.
+    ///
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Cdb::new(fs::read(path)?)
+    }
+}
+
+impl<T: AsRef<[u8]>> Cdb<T> {
+    ///
+    /// Creates a new reader over the bytes of a cdb file.
+    ///
+    pub fn new(data: T) -> io::Result<Self> {
+        if data.as_ref().len() < HEADER_SIZE as usize {
+            return Err(corrupt());
+        }
+        Ok(Cdb { data })
+    }
+    ///
+    /// Returns the data of the first record with the key.
+    ///
+    pub fn get(&self, key: &[u8]) -> io::Result<Option<&[u8]>> {
+        self.get_all(key).next().map_or(Ok(None), |d| d.map(Some))
+    }
+    ///
+    /// Iterates over the data of every record with the key in the order they
+    /// were added.
+    ///
+    pub fn get_all<'a, 'k>(&'a self, key: &'k [u8]) -> CdbFind<'a, 'k> {
+        let h = hash(key);
+        let data = self.data.as_ref();
+        let (table, len) = match read_pair(data, (h & 255) * 8) {
+            Ok(pair) => pair,
+            Err(e) => return CdbFind::failed(data, key, e),
+        };
+        CdbFind {
+            data,
+            key,
+            hash: h,
+            table,
+            len,
+            slot: if len == 0 { 0 } else { (h >> 8) % len },
+            tried: 0,
+            error: None,
+        }
+    }
+    ///
+    /// Iterates over every record in the order they were added.
+    ///
+    pub fn iter(&self) -> CdbIter<'_> {
+        let data = self.data.as_ref();
+        let end = read_u32(data, 0).unwrap_or(HEADER_SIZE);
+        CdbIter {
+            data,
+            pos: HEADER_SIZE,
+            end,
+        }
+    }
+    ///
+    /// Writes every record as `cdbdump` style text.
+    ///
+    pub fn dump_text<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for record in self.iter() {
+            let (key, data) = record?;
+            write!(out, "+{},{}:", key.len(), data.len())?;
+            out.write_all(key)?;
+            out.write_all(b"->")?;
+            out.write_all(data)?;
+            out.write_all(b"\n")?;
+        }
+        out.write_all(b"\n")
+    }
+}
+
+///
+/// Iterator over the data of the records with one key.
+///
+pub struct CdbFind<'a, 'k> {
+    data: &'a [u8],
+    key: &'k [u8],
+    hash: u32,
+    table: u32,
+    len: u32,
+    slot: u32,
+    tried: u32,
+    error: Option<io::Error>,
+}
+
+impl<'a, 'k> CdbFind<'a, 'k> {
+    fn failed(data: &'a [u8], key: &'k [u8], error: io::Error) -> Self {
+        CdbFind {
+            data,
+            key,
+            hash: 0,
+            table: 0,
+            len: 0,
+            slot: 0,
+            tried: 0,
+            error: Some(error),
+        }
+    }
+    fn find(&mut self) -> io::Result<Option<&'a [u8]>> {
+        while self.tried < self.len {
+            let (h, pos) = read_pair(
+                self.data,
+                self.table.wrapping_add(self.slot.wrapping_mul(8)),
+            )?;
+            self.tried += 1;
+            self.slot = (self.slot + 1) % self.len;
+            if pos == 0 {
+                self.tried = self.len;
+                break;
+            }
+            if h != self.hash {
+                continue;
+            }
+            let (key, data, _) = read_record(self.data, pos)?;
+            if key == self.key {
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'a, 'k> Iterator for CdbFind<'a, 'k> {
+    type Item = io::Result<&'a [u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.error.take() {
+            self.tried = self.len;
+            return Some(Err(error));
+        }
+        match self.find() {
+            Ok(found) => found.map(Ok),
+            Err(e) => {
+                self.tried = self.len;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+///
+/// Iterator over every key and data record in a file.
+///
+pub struct CdbIter<'a> {
+    data: &'a [u8],
+    pos: u32,
+    end: u32,
+}
+
+impl<'a> Iterator for CdbIter<'a> {
+    type Item = io::Result<(&'a [u8], &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.end {
+            return None;
+        }
+        match read_record(self.data, self.pos) {
+            Ok((key, data, next)) => {
+                self.pos = next;
+                Some(Ok((key, data)))
+            }
+            Err(e) => {
+                self.pos = self.end;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+fn read_u32(data: &[u8], pos: u32) -> io::Result<u32> {
+    let pos = pos as usize;
+    match data.get(pos..pos + 4) {
+        Some(b) => Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(corrupt()),
+    }
+}
+
+fn read_pair(data: &[u8], pos: u32) -> io::Result<(u32, u32)> {
+    Ok((
+        read_u32(data, pos)?,
+        read_u32(data, pos.checked_add(4).ok_or_else(corrupt)?)?,
+    ))
+}
+
+///
+/// Returns the key and data of the record at pos along with the position of
+/// the next record.
+///
+fn read_record(data: &[u8], pos: u32) -> io::Result<(&[u8], &[u8], u32)> {
+    let (klen, dlen) = read_pair(data, pos)?;
+    let start = pos as usize + 8;
+    let middle = start.checked_add(klen as usize).ok_or_else(corrupt)?;
+    let end = middle.checked_add(dlen as usize).ok_or_else(corrupt)?;
+    if end > data.len() || end > u32::MAX as usize {
+        return Err(corrupt());
+    }
+    Ok((&data[start..middle], &data[middle..end], end as u32))
+}
+
+fn to_u32(len: usize) -> io::Result<u32> {
+    if len > u32::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cdb record over 4GB",
+        ));
+    }
+    Ok(len as u32)
+}
+
+fn read_byte<R: BufRead>(input: &mut R) -> io::Result<u8> {
+    let mut byte = [0u8];
+    input.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+fn read_number<R: BufRead>(input: &mut R, end: u8) -> io::Result<usize> {
+    let mut number: usize = 0;
+    let mut digits = 0;
+    loop {
+        let byte = read_byte(input)?;
+        if byte == end && digits > 0 {
+            return Ok(number);
+        }
+        if !byte.is_ascii_digit() {
+            return Err(bad_text());
+        }
+        number = number
+            .checked_mul(10)
+            .and_then(|n| n.checked_add((byte - b'0') as usize))
+            .ok_or_else(bad_text)?;
+        digits += 1;
+    }
+}
+
+fn expect<R: BufRead>(input: &mut R, expected: &[u8]) -> io::Result<()> {
+    for &e in expected {
+        if read_byte(input)? != e {
+            return Err(bad_text());
+        }
+    }
+    Ok(())
+}
+
+fn corrupt() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "corrupt cdb file")
+}
+
+fn bad_text() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "bad cdb text format")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // test1.txt and the test1.cdb made from it by cdbmake come from the public
+    // domain cdb crate.
+    const TEXT: &[u8] = include_bytes!("../tests/fixtures/cdb/test1.txt");
+    const CDB: &[u8] = include_bytes!("../tests/fixtures/cdb/test1.cdb");
+
+    #[test]
+    fn it_does_read_and_write_correctly() {
+        let mut sut = CdbWriter::new(Cursor::new(Vec::new())).unwrap();
+        sut.add_text(TEXT).unwrap();
+        let bytes = sut.finish().unwrap().into_inner();
+        assert_eq!(&bytes[..], CDB);
+        let cdb = Cdb::new(CDB).unwrap();
+        assert_eq!(cdb.get(b"one").unwrap(), Some(&b"Hello"[..]));
+        assert_eq!(cdb.get(b"two").unwrap(), Some(&b"Goodbye"[..]));
+        assert_eq!(cdb.get(b"three").unwrap(), None);
+        let all: Vec<&[u8]> = cdb.get_all(b"one").map(|d| d.unwrap()).collect();
+        assert_eq!(all, vec![&b"Hello"[..], &b", World!"[..]]);
+        assert_eq!(cdb.iter().count(), 4);
+        let mut dump = Vec::new();
+        cdb.dump_text(&mut dump).unwrap();
+        assert_eq!(&dump[..], TEXT);
+        assert_eq!(hash(b"one"), 193_420_161);
+        let mut truncated = CDB.to_vec();
+        truncated.truncate(2100);
+        let cdb = Cdb::new(truncated).unwrap();
+        assert!(cdb.get(b"one").is_err());
+        assert!(Cdb::new(&b"short"[..]).is_err());
+        let mut sut = CdbWriter::new(Cursor::new(Vec::new())).unwrap();
+        assert!(sut.add_text(&b"+3,5:one=>Hello\n\n"[..]).is_err());
+    }
+}
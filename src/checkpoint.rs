@@ -0,0 +1,691 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Saving a hasher part way through and carrying on later.
+//!
+//! Every hasher in the crate implements `Resumable`. Its checkpoint() gives a
+//! `Checkpoint` holding which algorithm it is, the salt it started from, the
+//! running hash total, how much has been written and its `IntEncoding`, plus
+//! the few flags a hasher needs beyond that in `extra`. Restoring one gives a
+//! hasher that carries on exactly where the first stopped, so a long stream
+//! can be hashed across runs or machines and still give the one-shot hash.
+//!
+//! A checkpoint only restores into the algorithm that made it. The PHP
+//! variants share their running total with the plain hashes but finish
+//! differently, so restoring an `X33aPhp` checkpoint as an `X33a` is refused
+//! with `CheckpointError::WrongAlgorithm` instead of quietly giving the wrong
+//! hash.
+//!
+//! ## Binary format
+//!
+//! to_bytes() gives version 1 of a fixed layout, with every integer little
+//! endian:
+//!
+//! | Bytes | Field |
+//! |-------|-------|
+//! | 4 | The magic `DJBH` |
+//! | 1 | Format version, 1 |
+//! | 2 | `Algorithm::id()` |
+//! | 1 | `Algorithm::bits()` |
+//! | 1 | `IntEncoding`: 0 little endian, 1 big endian, 2 language |
+//! | 8 | Salt |
+//! | 8 | State, the running hash total |
+//! | 8 | Consumed, the count of bytes written, or steps for whole units |
+//! | 4 | Length of extra |
+//! | n | Extra |
+//!
+//! Extra is empty except for:
+//!
+//! * `GStrHash`, `TclHash` and `LibibertyHash`: 1 byte, 1 once a NUL byte has
+//!   ended the string.
+//! * `AprHash`: 2 bytes, 1 for `APR_HASH_KEY_STRING` keys and 1 once a NUL
+//!   byte has ended the key.
+//! * `PerlHash`: 1 byte, 1 for the signed variant.
+//! * `Py2StrHash` and `Py2StrHashU32`: the 8 byte suffix of the secret, the
+//!   salt being the prefix.
+//! * `LuaHash`: 1 byte for the version, 51, 52 or 53, the 4 byte
+//!   `LUAI_HASHLIMIT` and then every byte written so far. Lua hashes a string
+//!   from its end so there is no running total and the state is always 0.
+//!
+//! With the `serde` feature a `Checkpoint` also serializes as a struct with
+//! the same fields, the algorithm given by its name.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use djb_hash::checkpoint::*;
+//! use djb_hash::x33a_php::X33aPhp;
+//! use djb_hash::x33a::X33a;
+//! let mut hasher = X33aPhp::new();
+//! hasher.write(b"Hello, ");
+//! let saved = hasher.checkpoint().to_bytes();
+//! // Later, or somewhere else.
+//! let checkpoint = Checkpoint::from_bytes(&saved).unwrap();
+//! let mut resumed = X33aPhp::restore(&checkpoint).unwrap();
+//! resumed.write(b"World");
+//! let mut whole = X33aPhp::new();
+//! whole.write(b"Hello, World");
+//! assert_eq!(resumed.finish(), whole.finish());
+//! assert!(X33a::restore(&checkpoint).is_err());
+//! ```
+//!
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hasher;
+use Algorithm;
+use IntEncoding;
+
+///
+/// The magic bytes at the start of a binary checkpoint.
+///
+pub const MAGIC: [u8; 4] = *b"DJBH";
+
+///
+/// The version of the binary and serde formats written by this crate.
+///
+pub const VERSION: u8 = 1;
+
+const HEADER_LEN: usize = 37;
+
+///
+/// The saved state of a hasher.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "Repr", try_from = "Repr")
+)]
+pub struct Checkpoint {
+    ///
+    /// The hash that made the checkpoint.
+    ///
+    pub algorithm: Algorithm,
+    ///
+    /// The salt, or seed, the hash started from.
+    ///
+    pub salt: u64,
+    ///
+    /// The running hash total.
+    ///
+    pub state: u64,
+    ///
+    /// The number of bytes written, with each whole unit or integer written
+    /// as a single step counting as one.
+    ///
+    pub consumed: u64,
+    ///
+    /// How the integer write_*() functions add integers to the hash.
+    ///
+    pub int_encoding: IntEncoding,
+    ///
+    /// The rest of the state some hashes need, see the module docs.
+    ///
+    pub extra: Vec<u8>,
+}
+
+impl Checkpoint {
+    pub(crate) fn new(
+        algorithm: Algorithm,
+        salt: u64,
+        state: u64,
+        consumed: u64,
+        int_encoding: IntEncoding,
+    ) -> Self {
+        Checkpoint {
+            algorithm,
+            salt,
+            state,
+            consumed,
+            int_encoding,
+            extra: Vec::new(),
+        }
+    }
+    ///
+    /// Returns the checkpoint in the versioned binary format.
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.extra.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.algorithm.id().to_le_bytes());
+        bytes.push(self.algorithm.bits() as u8);
+        bytes.push(encoding_id(self.int_encoding));
+        bytes.extend_from_slice(&self.salt.to_le_bytes());
+        bytes.extend_from_slice(&self.state.to_le_bytes());
+        bytes.extend_from_slice(&self.consumed.to_le_bytes());
+        bytes.extend_from_slice(&(self.extra.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.extra);
+        bytes
+    }
+    ///
+    /// Reads a checkpoint from the binary format.
+    ///
+    /// Only the layout is checked here, whether the values make sense for the
+    /// algorithm is checked when the checkpoint is restored.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> Result<Checkpoint, CheckpointError> {
+        if bytes.len() < 5 {
+            return Err(CheckpointError::Truncated);
+        }
+        if bytes[..4] != MAGIC {
+            return Err(CheckpointError::BadMagic);
+        }
+        if bytes[4] != VERSION {
+            return Err(CheckpointError::UnsupportedVersion(bytes[4]));
+        }
+        if bytes.len() < HEADER_LEN {
+            return Err(CheckpointError::Truncated);
+        }
+        let u64_at = |at: usize| {
+            let mut buf = [0u8; 8];
+            buf.copy_from_slice(&bytes[at..at + 8]);
+            u64::from_le_bytes(buf)
+        };
+        let id = u16::from_le_bytes([bytes[5], bytes[6]]);
+        let algorithm = Algorithm::from_id(id).ok_or(CheckpointError::UnknownAlgorithm(id))?;
+        check_bits(algorithm, bytes[7] as u32)?;
+        let int_encoding = encoding_from_id(bytes[8])?;
+        let len = u32::from_le_bytes([bytes[33], bytes[34], bytes[35], bytes[36]]) as usize;
+        let extra = &bytes[HEADER_LEN..];
+        if extra.len() < len {
+            return Err(CheckpointError::Truncated);
+        }
+        if extra.len() > len {
+            return Err(CheckpointError::TrailingBytes);
+        }
+        Ok(Checkpoint {
+            algorithm,
+            salt: u64_at(9),
+            state: u64_at(17),
+            consumed: u64_at(25),
+            int_encoding,
+            extra: extra.to_vec(),
+        })
+    }
+    ///
+    /// Restores the hasher the checkpoint was made from, the same as
+    /// `R::restore()`.
+    ///
+    pub fn restore<R: Resumable>(&self) -> Result<R, CheckpointError> {
+        R::restore(self)
+    }
+    ///
+    /// Checks the checkpoint was made by the algorithm and that the salt and
+    /// state fit its width, returning the extra bytes.
+    ///
+    pub(crate) fn check(&self, algorithm: Algorithm) -> Result<&[u8], CheckpointError> {
+        if self.algorithm != algorithm {
+            return Err(CheckpointError::WrongAlgorithm {
+                expected: algorithm,
+                found: self.algorithm,
+            });
+        }
+        let bits = algorithm.bits();
+        if bits < 64 && (self.salt >> bits != 0 || self.state >> bits != 0) {
+            return Err(CheckpointError::OutOfRange);
+        }
+        Ok(&self.extra)
+    }
+    ///
+    /// Same as check() but also checks the length of the extra bytes.
+    ///
+    pub(crate) fn expect(
+        &self,
+        algorithm: Algorithm,
+        extra_len: usize,
+    ) -> Result<&[u8], CheckpointError> {
+        let extra = self.check(algorithm)?;
+        if extra.len() != extra_len {
+            return Err(CheckpointError::InvalidExtra);
+        }
+        Ok(extra)
+    }
+}
+
+///
+/// Reads a 0 or 1 flag byte from the extra bytes.
+///
+pub(crate) fn flag(byte: u8) -> Result<bool, CheckpointError> {
+    match byte {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(CheckpointError::InvalidExtra),
+    }
+}
+
+fn check_bits(algorithm: Algorithm, bits: u32) -> Result<(), CheckpointError> {
+    if bits != algorithm.bits() {
+        return Err(CheckpointError::WidthMismatch {
+            expected: algorithm.bits(),
+            found: bits,
+        });
+    }
+    Ok(())
+}
+
+fn encoding_id(encoding: IntEncoding) -> u8 {
+    match encoding {
+        IntEncoding::LittleEndian => 0,
+        IntEncoding::BigEndian => 1,
+        IntEncoding::Language => 2,
+    }
+}
+
+fn encoding_from_id(id: u8) -> Result<IntEncoding, CheckpointError> {
+    match id {
+        0 => Ok(IntEncoding::LittleEndian),
+        1 => Ok(IntEncoding::BigEndian),
+        2 => Ok(IntEncoding::Language),
+        _ => Err(CheckpointError::UnknownIntEncoding(id)),
+    }
+}
+
+///
+/// The serde form of a checkpoint, which carries the version and width so
+/// they can be checked when it is read back.
+///
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Checkpoint")]
+struct Repr {
+    version: u8,
+    algorithm: Algorithm,
+    bits: u32,
+    int_encoding: IntEncoding,
+    salt: u64,
+    state: u64,
+    consumed: u64,
+    extra: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Checkpoint> for Repr {
+    fn from(checkpoint: Checkpoint) -> Self {
+        Repr {
+            version: VERSION,
+            algorithm: checkpoint.algorithm,
+            bits: checkpoint.algorithm.bits(),
+            int_encoding: checkpoint.int_encoding,
+            salt: checkpoint.salt,
+            state: checkpoint.state,
+            consumed: checkpoint.consumed,
+            extra: checkpoint.extra,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Repr> for Checkpoint {
+    type Error = CheckpointError;
+    fn try_from(repr: Repr) -> Result<Self, Self::Error> {
+        if repr.version != VERSION {
+            return Err(CheckpointError::UnsupportedVersion(repr.version));
+        }
+        check_bits(repr.algorithm, repr.bits)?;
+        Ok(Checkpoint {
+            algorithm: repr.algorithm,
+            salt: repr.salt,
+            state: repr.state,
+            consumed: repr.consumed,
+            int_encoding: repr.int_encoding,
+            extra: repr.extra,
+        })
+    }
+}
+
+///
+/// Why a checkpoint could not be read or restored.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckpointError {
+    ///
+    /// The bytes do not start with `MAGIC`.
+    ///
+    BadMagic,
+    ///
+    /// The checkpoint is in a format version this crate does not know.
+    ///
+    UnsupportedVersion(u8),
+    ///
+    /// The algorithm id is not one of `Algorithm::ALL`.
+    ///
+    UnknownAlgorithm(u16),
+    ///
+    /// The integer encoding id is not one this crate knows.
+    ///
+    UnknownIntEncoding(u8),
+    ///
+    /// The width saved with the checkpoint is not the width of its algorithm.
+    ///
+    WidthMismatch { expected: u32, found: u32 },
+    ///
+    /// The bytes end before the checkpoint does.
+    ///
+    Truncated,
+    ///
+    /// There are bytes left after the checkpoint.
+    ///
+    TrailingBytes,
+    ///
+    /// The checkpoint was made by a different algorithm than the hasher it
+    /// was restored into.
+    ///
+    WrongAlgorithm {
+        expected: Algorithm,
+        found: Algorithm,
+    },
+    ///
+    /// The salt or state does not fit the width of the algorithm.
+    ///
+    OutOfRange,
+    ///
+    /// The extra bytes are not what the algorithm needs.
+    ///
+    InvalidExtra,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CheckpointError::BadMagic => f.write_str("not a checkpoint, bad magic bytes"),
+            CheckpointError::UnsupportedVersion(version) => {
+                write!(f, "unsupported checkpoint version {}", version)
+            }
+            CheckpointError::UnknownAlgorithm(id) => write!(f, "unknown algorithm id {}", id),
+            CheckpointError::UnknownIntEncoding(id) => {
+                write!(f, "unknown integer encoding id {}", id)
+            }
+            CheckpointError::WidthMismatch { expected, found } => write!(
+                f,
+                "checkpoint is {} bits but the algorithm is {} bits",
+                found, expected
+            ),
+            CheckpointError::Truncated => f.write_str("checkpoint is truncated"),
+            CheckpointError::TrailingBytes => f.write_str("bytes left after the checkpoint"),
+            CheckpointError::WrongAlgorithm { expected, found } => write!(
+                f,
+                "checkpoint of {} can not be restored as {}",
+                found, expected
+            ),
+            CheckpointError::OutOfRange => {
+                f.write_str("salt or state does not fit the width of the algorithm")
+            }
+            CheckpointError::InvalidExtra => f.write_str("invalid extra state for the algorithm"),
+        }
+    }
+}
+
+impl Error for CheckpointError {}
+
+///
+/// This trait is used by hashers that can be saved and restored part way
+/// through.
+///
+pub trait Resumable: Hasher + Sized {
+    ///
+    /// Returns the current state of the hasher.
+    ///
+    fn checkpoint(&self) -> Checkpoint;
+    ///
+    /// Creates a hasher that carries on from the checkpoint.
+    ///
+    /// Fails if the checkpoint was made by a different algorithm or its
+    /// values can not have come from this one.
+    ///
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apr::AprHash;
+    use elf_hash::ElfHash;
+    use g_str_hash::GStrHash;
+    use libiberty_hash::LibibertyHash;
+    use lua_hash::{LuaHash, LuaVersion};
+    use nginx::{NgxHashKey, NgxHashKeyLc};
+    use perl_hash::PerlHash;
+    use py2_str_hash::Py2StrHash;
+    use py2_str_hash_u32::Py2StrHashU32;
+    use redis::RedisCaseHash;
+    use sdbm::Sdbm;
+    use tcl_hash::TclHash;
+    use x33a::X33a;
+    use x33a_php::X33aPhp;
+    use x33a_php_signed::X33aPhpSigned;
+    use x33a_signed::X33aSigned;
+    use x33a_u32::X33aU32;
+    use x33a_u32_php::X33aU32Php;
+    use x33a_u32_php_signed::X33aU32PhpSigned;
+    use x33a_u32_signed::X33aU32Signed;
+    use x33x::X33x;
+    use x33x_u32::X33xU32;
+
+    fn resume<H: Resumable>(mut first: H, mut whole: H, algorithm: Algorithm) {
+        first.write(b"Hello, ");
+        first.write_u32(0x4579);
+        let checkpoint = first.checkpoint();
+        assert_eq!(checkpoint.algorithm, algorithm);
+        let bytes = checkpoint.to_bytes();
+        let mut sut = H::restore(&Checkpoint::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(sut.checkpoint(), checkpoint);
+        sut.write(b"World\0of hashes, long enough that Lua samples");
+        whole.write(b"Hello, ");
+        whole.write_u32(0x4579);
+        whole.write(b"World\0of hashes, long enough that Lua samples");
+        assert_eq!(sut.finish(), whole.finish(), "{}", algorithm);
+        assert_eq!(sut.checkpoint(), whole.checkpoint(), "{}", algorithm);
+    }
+
+    #[test]
+    fn it_does_resume_correctly() {
+        let language = IntEncoding::Language;
+        resume(X33a::new(), X33a::new(), Algorithm::X33a);
+        resume(
+            X33aPhp::new_with_salt(7919),
+            X33aPhp::new_with_salt(7919),
+            Algorithm::X33aPhp,
+        );
+        resume(X33aU32::new(), X33aU32::new(), Algorithm::X33aU32);
+        resume(
+            X33aU32Php::new().int_encoding(language),
+            X33aU32Php::new().int_encoding(language),
+            Algorithm::X33aU32Php,
+        );
+        resume(X33x::new(), X33x::new(), Algorithm::X33x);
+        resume(X33xU32::new(), X33xU32::new(), Algorithm::X33xU32);
+        resume(X33aSigned::new(), X33aSigned::new(), Algorithm::X33aSigned);
+        resume(
+            X33aPhpSigned::new(),
+            X33aPhpSigned::new(),
+            Algorithm::X33aPhpSigned,
+        );
+        resume(
+            X33aU32Signed::new(),
+            X33aU32Signed::new(),
+            Algorithm::X33aU32Signed,
+        );
+        resume(
+            X33aU32PhpSigned::new().int_encoding(IntEncoding::BigEndian),
+            X33aU32PhpSigned::new().int_encoding(IntEncoding::BigEndian),
+            Algorithm::X33aU32PhpSigned,
+        );
+        resume(ElfHash::new(), ElfHash::new(), Algorithm::ElfHash);
+        resume(GStrHash::new(), GStrHash::new(), Algorithm::GStrHash);
+        resume(
+            AprHash::new_key_string(),
+            AprHash::new_key_string(),
+            Algorithm::AprHash,
+        );
+        resume(
+            RedisCaseHash::new().int_encoding(language),
+            RedisCaseHash::new().int_encoding(language),
+            Algorithm::RedisCaseHash,
+        );
+        resume(
+            LuaHash::new_with_salt(LuaVersion::Lua52, 0x1234_5678).hash_limit(3),
+            LuaHash::new_with_salt(LuaVersion::Lua52, 0x1234_5678).hash_limit(3),
+            Algorithm::LuaHash,
+        );
+        resume(NgxHashKey::new(), NgxHashKey::new(), Algorithm::NgxHashKey);
+        resume(
+            NgxHashKeyLc::new(),
+            NgxHashKeyLc::new(),
+            Algorithm::NgxHashKeyLc,
+        );
+        resume(
+            PerlHash::new_signed(),
+            PerlHash::new_signed(),
+            Algorithm::PerlHash,
+        );
+        resume(
+            Py2StrHash::new_with_secret(17, 42),
+            Py2StrHash::new_with_secret(17, 42),
+            Algorithm::Py2StrHash,
+        );
+        resume(
+            Py2StrHashU32::new_with_secret(17, 42),
+            Py2StrHashU32::new_with_secret(17, 42),
+            Algorithm::Py2StrHashU32,
+        );
+        resume(Sdbm::new(), Sdbm::new(), Algorithm::Sdbm);
+        resume(TclHash::new(), TclHash::new(), Algorithm::TclHash);
+        resume(
+            LibibertyHash::new(),
+            LibibertyHash::new(),
+            Algorithm::LibibertyHash,
+        );
+    }
+
+    #[test]
+    fn it_does_refuse_bad_checkpoints_correctly() {
+        let mut hasher = X33aU32Php::new();
+        hasher.write(b"Ez");
+        let checkpoint = hasher.checkpoint();
+        assert_eq!(
+            X33aU32::restore(&checkpoint).err(),
+            Some(CheckpointError::WrongAlgorithm {
+                expected: Algorithm::X33aU32,
+                found: Algorithm::X33aU32Php,
+            })
+        );
+        assert!(checkpoint.restore::<X33aPhp>().is_err());
+        assert!(checkpoint.restore::<X33aU32PhpSigned>().is_err());
+        assert!(checkpoint.restore::<X33aU32Php>().is_ok());
+        let bytes = checkpoint.to_bytes();
+        assert_eq!(&bytes[..9], b"DJBH\x01\x03\x00\x20\x00");
+        assert_eq!(bytes.len(), 37);
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert_eq!(Checkpoint::from_bytes(&bad), Err(CheckpointError::BadMagic));
+        bad = bytes.clone();
+        bad[4] = 2;
+        assert_eq!(
+            Checkpoint::from_bytes(&bad),
+            Err(CheckpointError::UnsupportedVersion(2))
+        );
+        bad = bytes.clone();
+        bad[5] = 200;
+        assert_eq!(
+            Checkpoint::from_bytes(&bad),
+            Err(CheckpointError::UnknownAlgorithm(200))
+        );
+        bad = bytes.clone();
+        bad[7] = 64;
+        assert_eq!(
+            Checkpoint::from_bytes(&bad),
+            Err(CheckpointError::WidthMismatch {
+                expected: 32,
+                found: 64,
+            })
+        );
+        assert_eq!(
+            Checkpoint::from_bytes(&bytes[..36]),
+            Err(CheckpointError::Truncated)
+        );
+        bad = bytes.clone();
+        bad.push(0);
+        assert_eq!(
+            Checkpoint::from_bytes(&bad),
+            Err(CheckpointError::TrailingBytes)
+        );
+        let wide = Checkpoint {
+            state: 1 << 32,
+            ..checkpoint.clone()
+        };
+        assert_eq!(
+            X33aU32Php::restore(&wide).err(),
+            Some(CheckpointError::OutOfRange)
+        );
+        let extra = Checkpoint {
+            extra: vec![1],
+            ..checkpoint.clone()
+        };
+        assert_eq!(
+            X33aU32Php::restore(&extra).err(),
+            Some(CheckpointError::InvalidExtra)
+        );
+        let mut hasher = GStrHash::new();
+        hasher.write(b"Ez");
+        let flag = Checkpoint {
+            extra: vec![2],
+            ..hasher.checkpoint()
+        };
+        assert_eq!(
+            GStrHash::restore(&flag).err(),
+            Some(CheckpointError::InvalidExtra)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_does_serde_correctly() {
+        let mut hasher = X33aPhp::new();
+        hasher.write(b"Ez");
+        let checkpoint = hasher.checkpoint();
+        let json = serde_json::to_string(&checkpoint).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":1,"algorithm":"x33a_php","bits":64,"int_encoding":"LittleEndian","salt":5381,"state":5862308,"consumed":2,"extra":[]}"#
+        );
+        let back: Checkpoint = serde_json::from_str(&json).unwrap();
+        let mut sut = X33aPhp::restore(&back).unwrap();
+        sut.write(b"Ez");
+        let mut whole = X33aPhp::new();
+        whole.write(b"EzEz");
+        assert_eq!(sut.finish(), whole.finish());
+        assert!(serde_json::from_str::<Checkpoint>(&json.replace(":1,", ":2,")).is_err());
+        assert!(serde_json::from_str::<Checkpoint>(&json.replace(":64,", ":32,")).is_err());
+        assert!(serde_json::from_str::<Checkpoint>(&json.replace("x33a_php", "x33")).is_err());
+    }
+}
@@ -0,0 +1,434 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Minimal ELF reader shared by the symbol hash table modules.
+//!
+//! Only what is needed to find the hash table sections and the dynamic symbol
+//! names is read. Both ELFCLASS32 and ELFCLASS64 files in either byte order
+//! are supported.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::elf::*;
+//! let bytes = include_bytes!("../tests/fixtures/elf/libdjb64-gnu.so");
+//! let elf = Elf::parse(&bytes[..]).unwrap();
+//! assert_eq!(elf.class(), ElfClass::Elf64);
+//! assert_eq!(elf.endian(), Endian::Little);
+//! assert!(elf.section(b".gnu.hash").is_some());
+//! let symbols = elf.dynamic_symbols().unwrap();
+//! assert_eq!(symbols[0], &b""[..]);
+//! assert!(symbols.contains(&&b"djb_alpha"[..]));
+//! ```
+//!
+use std::error::Error;
+use std::fmt;
+
+///
+/// Section type of a SysV `.hash` section.
+///
+pub const SHT_HASH: u32 = 5;
+///
+/// Section type of the dynamic symbol table.
+///
+pub const SHT_DYNSYM: u32 = 11;
+///
+/// Section type of a GNU `.gnu.hash` section.
+///
+pub const SHT_GNU_HASH: u32 = 0x6fff_fff6;
+
+///
+/// The ELF file class which sets the size of addresses and some words.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ElfClass {
+    Elf32,
+    Elf64,
+}
+
+impl ElfClass {
+    ///
+    /// Returns the size of a native word in bits.
+    ///
+    pub fn bits(self) -> u32 {
+        match self {
+            ElfClass::Elf32 => 32,
+            ElfClass::Elf64 => 64,
+        }
+    }
+}
+
+///
+/// The byte order of the words in the file.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    pub(crate) fn read_u16(self, data: &[u8], pos: usize) -> Result<u16, ElfError> {
+        let b = bytes(data, pos, 2)?;
+        Ok(match self {
+            Endian::Little => u16::from_le_bytes([b[0], b[1]]),
+            Endian::Big => u16::from_be_bytes([b[0], b[1]]),
+        })
+    }
+    pub(crate) fn read_u32(self, data: &[u8], pos: usize) -> Result<u32, ElfError> {
+        let b = bytes(data, pos, 4)?;
+        let b = [b[0], b[1], b[2], b[3]];
+        Ok(match self {
+            Endian::Little => u32::from_le_bytes(b),
+            Endian::Big => u32::from_be_bytes(b),
+        })
+    }
+    pub(crate) fn read_u64(self, data: &[u8], pos: usize) -> Result<u64, ElfError> {
+        let b = bytes(data, pos, 8)?;
+        let b = [b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]];
+        Ok(match self {
+            Endian::Little => u64::from_le_bytes(b),
+            Endian::Big => u64::from_be_bytes(b),
+        })
+    }
+    ///
+    /// Reads a word that is 32 or 64 bits depending on the class.
+    ///
+    pub(crate) fn read_word(
+        self,
+        class: ElfClass,
+        data: &[u8],
+        pos: usize,
+    ) -> Result<u64, ElfError> {
+        match class {
+            ElfClass::Elf32 => self.read_u32(data, pos).map(u64::from),
+            ElfClass::Elf64 => self.read_u64(data, pos),
+        }
+    }
+    pub(crate) fn write_u32(self, out: &mut Vec<u8>, value: u32) {
+        match self {
+            Endian::Little => out.extend_from_slice(&value.to_le_bytes()),
+            Endian::Big => out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+    ///
+    /// Writes a word that is 32 or 64 bits depending on the class.
+    ///
+    pub(crate) fn write_word(self, class: ElfClass, out: &mut Vec<u8>, value: u64) {
+        match (class, self) {
+            (ElfClass::Elf32, _) => self.write_u32(out, value as u32),
+            (ElfClass::Elf64, Endian::Little) => out.extend_from_slice(&value.to_le_bytes()),
+            (ElfClass::Elf64, Endian::Big) => out.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+}
+
+///
+/// Errors from reading ELF files and their hash table sections.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ElfError {
+    ///
+    /// The data does not start with an ELF header this reader understands.
+    ///
+    NotElf,
+    ///
+    /// Something points past the end of the data.
+    ///
+    Truncated,
+    ///
+    /// A needed section is not in the file.
+    ///
+    MissingSection(&'static str),
+    ///
+    /// A section has values that can not be used.
+    ///
+    Malformed(&'static str),
+}
+
+impl fmt::Display for ElfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ElfError::NotElf => write!(f, "not a supported ELF file"),
+            ElfError::Truncated => write!(f, "ELF data is truncated"),
+            ElfError::MissingSection(name) => write!(f, "ELF file has no {} section", name),
+            ElfError::Malformed(what) => write!(f, "malformed ELF {}", what),
+        }
+    }
+}
+
+impl Error for ElfError {}
+
+///
+/// One section from the section header table.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Section<'a> {
+    pub name: &'a [u8],
+    pub kind: u32,
+    pub link: u32,
+    pub entsize: u64,
+    pub data: &'a [u8],
+}
+
+///
+/// ELF file held in memory along with its section headers.
+///
+#[derive(Clone, Debug)]
+pub struct Elf<'a> {
+    class: ElfClass,
+    endian: Endian,
+    sections: Vec<Section<'a>>,
+}
+
+impl<'a> Elf<'a> {
+    ///
+    /// Reads the ELF and section headers.
+    ///
+    /// Sections without file data, like `.bss`, are given empty data.
+    ///
+    pub fn parse(data: &'a [u8]) -> Result<Self, ElfError> {
+        if data.len() < 16 || &data[..4] != b"\x7fELF" {
+            return Err(ElfError::NotElf);
+        }
+        let class = match data[4] {
+            1 => ElfClass::Elf32,
+            2 => ElfClass::Elf64,
+            _ => return Err(ElfError::NotElf),
+        };
+        let endian = match data[5] {
+            1 => Endian::Little,
+            2 => Endian::Big,
+            _ => return Err(ElfError::NotElf),
+        };
+        let (shoff, shentsize, shnum, shstrndx) = match class {
+            ElfClass::Elf32 => (
+                u64::from(endian.read_u32(data, 0x20)?),
+                endian.read_u16(data, 0x2e)?,
+                endian.read_u16(data, 0x30)?,
+                endian.read_u16(data, 0x32)?,
+            ),
+            ElfClass::Elf64 => (
+                endian.read_u64(data, 0x28)?,
+                endian.read_u16(data, 0x3a)?,
+                endian.read_u16(data, 0x3c)?,
+                endian.read_u16(data, 0x3e)?,
+            ),
+        };
+        let min_entsize = match class {
+            ElfClass::Elf32 => 40,
+            ElfClass::Elf64 => 64,
+        };
+        if shnum > 0 && shentsize < min_entsize {
+            return Err(ElfError::Malformed("section header size"));
+        }
+        let mut headers = Vec::with_capacity(shnum as usize);
+        for i in 0..shnum as u64 {
+            let pos = to_usize(shoff.checked_add(i * shentsize as u64))?;
+            let w = class.bits() as usize / 8;
+            let name = endian.read_u32(data, pos)?;
+            let kind = endian.read_u32(data, pos + 4)?;
+            let offset = endian.read_word(class, data, pos + 8 + 2 * w)?;
+            let size = endian.read_word(class, data, pos + 8 + 3 * w)?;
+            let link = endian.read_u32(data, pos + 8 + 4 * w)?;
+            let entsize = endian.read_word(class, data, pos + 16 + 5 * w)?;
+            // SHT_NOBITS sections take no room in the file.
+            let contents = if kind == 8 {
+                &data[..0]
+            } else {
+                let start = to_usize(Some(offset))?;
+                let end = to_usize(offset.checked_add(size))?;
+                data.get(start..end).ok_or(ElfError::Truncated)?
+            };
+            headers.push((
+                name,
+                Section {
+                    name: &data[..0],
+                    kind,
+                    link,
+                    entsize,
+                    data: contents,
+                },
+            ));
+        }
+        let names = match headers.get(shstrndx as usize) {
+            Some((_, section)) => section.data,
+            None if shnum == 0 => &data[..0],
+            None => return Err(ElfError::Malformed("section name table index")),
+        };
+        let mut sections = Vec::with_capacity(headers.len());
+        for (name, mut section) in headers {
+            section.name = c_str(names, name as usize)?;
+            sections.push(section);
+        }
+        Ok(Elf {
+            class,
+            endian,
+            sections,
+        })
+    }
+    ///
+    /// Returns the class of the file.
+    ///
+    pub fn class(&self) -> ElfClass {
+        self.class
+    }
+    ///
+    /// Returns the byte order of the file.
+    ///
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+    ///
+    /// Returns every section in section header table order.
+    ///
+    pub fn sections(&self) -> &[Section<'a>] {
+        &self.sections
+    }
+    ///
+    /// Returns the first section with the name.
+    ///
+    pub fn section(&self, name: &[u8]) -> Option<&Section<'a>> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+    ///
+    /// Returns the first section of the type.
+    ///
+    pub fn section_by_type(&self, kind: u32) -> Option<&Section<'a>> {
+        self.sections.iter().find(|s| s.kind == kind)
+    }
+    ///
+    /// Returns the names of the dynamic symbols indexed by symbol number.
+    ///
+    /// The result is what the lookup functions of the hash table modules
+    /// expect for their `symbols` argument.
+    ///
+    pub fn dynamic_symbols(&self) -> Result<Vec<&'a [u8]>, ElfError> {
+        let dynsym = self
+            .section_by_type(SHT_DYNSYM)
+            .ok_or(ElfError::MissingSection(".dynsym"))?;
+        let strtab = self
+            .sections
+            .get(dynsym.link as usize)
+            .ok_or(ElfError::Malformed("dynamic symbol string table link"))?;
+        let entsize = match (dynsym.entsize, self.class) {
+            (0, ElfClass::Elf32) => 16,
+            (0, ElfClass::Elf64) => 24,
+            (size, _) => to_usize(Some(size))?,
+        };
+        if entsize < 4 {
+            return Err(ElfError::Malformed("symbol entry size"));
+        }
+        dynsym
+            .data
+            .chunks(entsize)
+            .filter(|entry| entry.len() == entsize)
+            .map(|entry| {
+                let name = self.endian.read_u32(entry, 0)?;
+                c_str(strtab.data, name as usize)
+            })
+            .collect()
+    }
+}
+
+///
+/// Returns the bucket count GNU ld uses for the number of hashed symbols in
+/// both `.hash` and `.gnu.hash` sections, which for a `.gnu.hash` is never
+/// less than 2.
+///
+pub(crate) fn bucket_count(count: u32, gnu: bool) -> u32 {
+    const SIZES: [u32; 16] = [
+        1, 3, 17, 37, 67, 97, 131, 197, 263, 521, 1031, 2053, 4099, 8209, 16411, 32771,
+    ];
+    let mut best = 1;
+    for (i, &size) in SIZES.iter().enumerate() {
+        best = size;
+        if SIZES.get(i + 1).map_or(true, |&next| count < next) {
+            break;
+        }
+    }
+    if gnu {
+        best.max(2)
+    } else {
+        best
+    }
+}
+
+fn bytes(data: &[u8], pos: usize, len: usize) -> Result<&[u8], ElfError> {
+    pos.checked_add(len)
+        .and_then(|end| data.get(pos..end))
+        .ok_or(ElfError::Truncated)
+}
+
+fn to_usize(value: Option<u64>) -> Result<usize, ElfError> {
+    match value {
+        Some(v) if v <= usize::MAX as u64 => Ok(v as usize),
+        _ => Err(ElfError::Truncated),
+    }
+}
+
+///
+/// Returns the NUL terminated string starting at pos.
+///
+fn c_str(data: &[u8], pos: usize) -> Result<&[u8], ElfError> {
+    let rest = data.get(pos..).ok_or(ElfError::Truncated)?;
+    match rest.iter().position(|&b| b == 0) {
+        Some(end) => Ok(&rest[..end]),
+        None => Err(ElfError::Truncated),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ELF32: &[u8] = include_bytes!("../tests/fixtures/elf/libdjb32-gnu.so");
+    const ELF64: &[u8] = include_bytes!("../tests/fixtures/elf/libdjb64-gnu.so");
+
+    #[test]
+    fn it_does_parse_correctly() {
+        for &(data, class) in &[(ELF32, ElfClass::Elf32), (ELF64, ElfClass::Elf64)] {
+            let sut = Elf::parse(data).unwrap();
+            assert_eq!(sut.class(), class);
+            assert_eq!(sut.endian(), Endian::Little);
+            assert_eq!(sut.sections().len(), 8);
+            let gnu_hash = sut.section(b".gnu.hash").unwrap();
+            assert_eq!(gnu_hash.kind, SHT_GNU_HASH);
+            assert_eq!(gnu_hash.data.len(), 236);
+            let symbols = sut.dynamic_symbols().unwrap();
+            assert_eq!(symbols.len(), 31);
+            assert_eq!(symbols[1], &b"dl_new_hash"[..]);
+        }
+        assert_eq!(
+            Elf::parse(b"not an elf file").unwrap_err(),
+            ElfError::NotElf
+        );
+        assert_eq!(Elf::parse(&ELF64[..64]).unwrap_err(), ElfError::Truncated);
+    }
+}
@@ -0,0 +1,396 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Parser and builder for the GNU `.gnu.hash` ELF symbol hash table section.
+//!
+//! The hash glibc uses for this section, `dl_new_hash`, is exactly X33aU32
+//! with the usual 5381 starting value. The section is made up of a small
+//! header, a bloom filter of native sized words, the bucket array and the
+//! chain array:
+//!
+//! ```text
+//! nbuckets, symoffset, bloom_size, bloom_shift: u32
+//! bloom: [u32 or u64; bloom_size]
+//! buckets: [u32; nbuckets]
+//! chains: [u32; dynamic symbols - symoffset]
+//! ```
+//!
+//! Symbols that are in the table have to be sorted by bucket in the dynamic
+//! symbol table, which is why the builder also returns the order the symbols
+//! must be written in.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::elf::*;
+//! use djb_hash::elf_gnu_hash::*;
+//! let bytes = include_bytes!("../tests/fixtures/elf/libdjb64-gnu.so");
+//! let elf = Elf::parse(&bytes[..]).unwrap();
+//! let symbols = elf.dynamic_symbols().unwrap();
+//! let table = GnuHash::from_elf(&elf).unwrap();
+//! let index = table.lookup(b"djb_alpha", &symbols).unwrap();
+//! assert_eq!(symbols[index as usize], &b"djb_alpha"[..]);
+//! assert_eq!(table.lookup(b"djb_omega", &symbols), None);
+//! ```
+//!
+use elf::{bucket_count, Elf, ElfClass, ElfError, Endian, SHT_GNU_HASH};
+use std::hash::Hasher;
+use x33a_u32::X33aU32;
+use HasherU32;
+
+///
+/// Returns the GNU hash of the symbol name.
+///
+pub fn gnu_hash(name: &[u8]) -> u32 {
+    let mut hasher = X33aU32::new();
+    hasher.write(name);
+    hasher.finish_u32()
+}
+
+///
+/// A parsed or built `.gnu.hash` section.
+///
+/// For ELFCLASS32 only the low 32 bits of each bloom word are used.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GnuHash {
+    pub class: ElfClass,
+    pub symoffset: u32,
+    pub bloom_shift: u32,
+    pub bloom: Vec<u64>,
+    pub buckets: Vec<u32>,
+    pub chains: Vec<u32>,
+}
+
+impl GnuHash {
+    ///
+    /// Parses the contents of a `.gnu.hash` section.
+    ///
+    /// The section does not say how many symbols are in the chain array so
+    /// everything after the buckets is taken to be chains, like the section
+    /// linkers write.
+    ///
+    pub fn parse(section: &[u8], class: ElfClass, endian: Endian) -> Result<Self, ElfError> {
+        let nbuckets = endian.read_u32(section, 0)? as usize;
+        let symoffset = endian.read_u32(section, 4)?;
+        let bloom_size = endian.read_u32(section, 8)? as usize;
+        let bloom_shift = endian.read_u32(section, 12)?;
+        if nbuckets == 0 {
+            return Err(ElfError::Malformed("gnu hash bucket count"));
+        }
+        if !bloom_size.is_power_of_two() {
+            return Err(ElfError::Malformed("gnu hash bloom filter size"));
+        }
+        let word = class.bits() as usize / 8;
+        let mut pos = 16;
+        let mut bloom = Vec::with_capacity(bloom_size.min(section.len() / word));
+        for _ in 0..bloom_size {
+            bloom.push(endian.read_word(class, section, pos)?);
+            pos += word;
+        }
+        let mut buckets = Vec::with_capacity(nbuckets.min(section.len() / 4));
+        for _ in 0..nbuckets {
+            buckets.push(endian.read_u32(section, pos)?);
+            pos += 4;
+        }
+        let mut chains = Vec::with_capacity((section.len() - pos) / 4);
+        while pos + 4 <= section.len() {
+            chains.push(endian.read_u32(section, pos)?);
+            pos += 4;
+        }
+        Ok(GnuHash {
+            class,
+            symoffset,
+            bloom_shift,
+            bloom,
+            buckets,
+            chains,
+        })
+    }
+    ///
+    /// Finds and parses the `.gnu.hash` section of an ELF file.
+    ///
+    pub fn from_elf(elf: &Elf) -> Result<Self, ElfError> {
+        let section = elf
+            .section_by_type(SHT_GNU_HASH)
+            .ok_or(ElfError::MissingSection(".gnu.hash"))?;
+        GnuHash::parse(section.data, elf.class(), elf.endian())
+    }
+    ///
+    /// Checks the bloom filter for the hash of a symbol name.
+    ///
+    /// False means the symbol is certainly not in the table while true means
+    /// the chain has to be searched.
+    ///
+    pub fn may_contain(&self, hash: u32) -> bool {
+        let bits = self.class.bits();
+        let word = match self
+            .bloom
+            .get(((hash / bits) as usize) & (self.bloom.len().wrapping_sub(1)))
+        {
+            Some(&word) => word,
+            None => return false,
+        };
+        let mask = (1u64 << (hash % bits)) | (1u64 << (hash.wrapping_shr(self.bloom_shift) % bits));
+        word & mask == mask
+    }
+    ///
+    /// Returns the dynamic symbol index of the name.
+    ///
+    /// `symbols` are the names of the dynamic symbols indexed by symbol number
+    /// as returned by `Elf::dynamic_symbols()`.
+    ///
+    pub fn lookup<S: AsRef<[u8]>>(&self, name: &[u8], symbols: &[S]) -> Option<u32> {
+        let hash = gnu_hash(name);
+        if !self.may_contain(hash) {
+            return None;
+        }
+        let mut index = *self.buckets.get((hash as usize) % self.buckets.len())?;
+        if index < self.symoffset {
+            return None;
+        }
+        loop {
+            let chain = *self.chains.get((index - self.symoffset) as usize)?;
+            if chain | 1 == hash | 1 && symbols.get(index as usize)?.as_ref() == name {
+                return Some(index);
+            }
+            if chain & 1 == 1 {
+                return None;
+            }
+            index = index.checked_add(1)?;
+        }
+    }
+    ///
+    /// Returns the contents of the section in the given byte order.
+    ///
+    pub fn to_bytes(&self, endian: Endian) -> Vec<u8> {
+        let word = self.class.bits() as usize / 8;
+        let mut out = Vec::with_capacity(
+            16 + self.bloom.len() * word + (self.buckets.len() + self.chains.len()) * 4,
+        );
+        endian.write_u32(&mut out, self.buckets.len() as u32);
+        endian.write_u32(&mut out, self.symoffset);
+        endian.write_u32(&mut out, self.bloom.len() as u32);
+        endian.write_u32(&mut out, self.bloom_shift);
+        for &word in &self.bloom {
+            endian.write_word(self.class, &mut out, word);
+        }
+        for &bucket in &self.buckets {
+            endian.write_u32(&mut out, bucket);
+        }
+        for &chain in &self.chains {
+            endian.write_u32(&mut out, chain);
+        }
+        out
+    }
+}
+
+///
+/// Builds `.gnu.hash` sections from symbol names.
+///
+/// By default the table is sized the same way GNU ld sizes it.
+///
+#[derive(Clone, Debug)]
+pub struct GnuHashBuilder {
+    class: ElfClass,
+    symoffset: u32,
+    buckets: Option<u32>,
+    bloom_shift: Option<u32>,
+}
+
+impl GnuHashBuilder {
+    ///
+    /// Creates a new builder for the ELF class.
+    ///
+    /// The symbol offset defaults to one, which skips only the null symbol.
+    ///
+    pub fn new(class: ElfClass) -> Self {
+        GnuHashBuilder {
+            class,
+            symoffset: 1,
+            buckets: None,
+            bloom_shift: None,
+        }
+    }
+    ///
+    /// Sets the index of the first dynamic symbol in the table.
+    ///
+    /// Symbols before it, like undefined ones, can not be looked up.
+    ///
+    pub fn symoffset(mut self, symoffset: u32) -> Self {
+        self.symoffset = symoffset;
+        self
+    }
+    ///
+    /// Sets the number of buckets instead of using the one GNU ld would pick.
+    ///
+    pub fn buckets(mut self, buckets: u32) -> Self {
+        self.buckets = Some(buckets.max(1));
+        self
+    }
+    ///
+    /// Sets the bloom filter shift instead of using the one GNU ld would pick.
+    ///
+    pub fn bloom_shift(mut self, bloom_shift: u32) -> Self {
+        self.bloom_shift = Some(bloom_shift);
+        self
+    }
+    ///
+    /// Builds the section for the names.
+    ///
+    /// Also returns the order the names have to be given in the dynamic symbol
+    /// table starting at the symbol offset, as indexes into `names`.
+    ///
+    pub fn build<S: AsRef<[u8]>>(&self, names: &[S]) -> (GnuHash, Vec<usize>) {
+        let count = names.len() as u32;
+        // bfd writes an empty `.gnu.hash` as one empty bucket and a one word
+        // bloom filter with a shift of 0.
+        let nbuckets = self.buckets.unwrap_or_else(|| {
+            if count == 0 {
+                1
+            } else {
+                bucket_count(count, true)
+            }
+        });
+        let bits = self.class.bits();
+        let shift1 = bits.trailing_zeros();
+        // Same as bfd: about two bloom bits per symbol, rounded to a power of
+        // two and never less than one word.
+        let log2 = if count == 0 {
+            0
+        } else {
+            31 - count.leading_zeros()
+        } + 1;
+        let mut maskbitslog2 = if log2 < 3 {
+            5
+        } else if (1 << (log2 - 2)) & count != 0 {
+            log2 + 3
+        } else {
+            log2 + 2
+        };
+        maskbitslog2 = maskbitslog2.max(shift1);
+        let bloom_shift = self
+            .bloom_shift
+            .unwrap_or(if count == 0 { 0 } else { maskbitslog2 });
+        let mut bloom = vec![0u64; 1 << (maskbitslog2 - shift1)];
+        let hashes: Vec<u32> = names.iter().map(|n| gnu_hash(n.as_ref())).collect();
+        let mut order: Vec<usize> = (0..names.len()).collect();
+        order.sort_by_key(|&i| hashes[i] % nbuckets);
+        let mut buckets = vec![0u32; nbuckets as usize];
+        let mut chains = Vec::with_capacity(names.len());
+        let words = bloom.len();
+        for (position, &i) in order.iter().enumerate() {
+            let hash = hashes[i];
+            bloom[((hash / bits) as usize) & (words - 1)] |=
+                (1u64 << (hash % bits)) | (1u64 << (hash.wrapping_shr(bloom_shift) % bits));
+            let bucket = (hash % nbuckets) as usize;
+            if buckets[bucket] == 0 {
+                buckets[bucket] = self.symoffset + position as u32;
+            }
+            let last = order
+                .get(position + 1)
+                .map_or(true, |&next| hashes[next] % nbuckets != bucket as u32);
+            chains.push(if last { hash | 1 } else { hash & !1 });
+        }
+        let table = GnuHash {
+            class: self.class,
+            symoffset: self.symoffset,
+            bloom_shift,
+            bloom,
+            buckets,
+            chains,
+        };
+        (table, order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ELF32: &[u8] = include_bytes!("../tests/fixtures/elf/libdjb32-gnu.so");
+    const ELF64: &[u8] = include_bytes!("../tests/fixtures/elf/libdjb64-gnu.so");
+
+    fn names_of(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("djb_{}", i)).collect()
+    }
+
+    #[test]
+    fn it_does_lookup_and_build_correctly() {
+        assert_eq!(gnu_hash(b""), 5381);
+        assert_eq!(gnu_hash(b"printf"), 0x156b_2bb8);
+        for &data in &[ELF32, ELF64] {
+            let elf = Elf::parse(data).unwrap();
+            let section = elf.section(b".gnu.hash").unwrap().data;
+            let symbols = elf.dynamic_symbols().unwrap();
+            let sut = GnuHash::from_elf(&elf).unwrap();
+            assert_eq!(sut.buckets.len(), 17);
+            assert_eq!(sut.symoffset, 1);
+            assert_eq!(sut.chains.len(), 30);
+            for (i, name) in symbols.iter().enumerate().skip(1) {
+                assert_eq!(sut.lookup(name, &symbols), Some(i as u32));
+            }
+            assert_eq!(sut.lookup(b"", &symbols), None);
+            assert_eq!(sut.lookup(b"djb_omega", &symbols), None);
+            assert_eq!(sut.to_bytes(Endian::Little), section);
+            // The fixtures were linked by GNU ld so building from the same
+            // names has to give back the same section.
+            let (built, order) = GnuHashBuilder::new(elf.class()).build(&symbols[1..]);
+            assert_eq!(built, sut);
+            assert_eq!(order, (0..30).collect::<Vec<_>>());
+            let big = built.to_bytes(Endian::Big);
+            assert_eq!(GnuHash::parse(&big, elf.class(), Endian::Big).unwrap(), sut);
+        }
+        let names = ["alpha", "beta", "gamma", "delta", "epsilon"];
+        let (sut, order) = GnuHashBuilder::new(ElfClass::Elf32)
+            .symoffset(3)
+            .buckets(2)
+            .build(&names);
+        let mut symbols = vec!["", "undefined", "local"];
+        symbols.extend(order.iter().map(|&i| names[i]));
+        for name in &names {
+            assert_eq!(
+                symbols[sut.lookup(name.as_bytes(), &symbols).unwrap() as usize],
+                *name
+            );
+        }
+        assert_eq!(sut.lookup(b"undefined", &symbols), None);
+        // GNU ld uses 2 buckets for 1 or 2 symbols, and 3 from 3 symbols on.
+        for &(count, buckets) in &[(1, 2), (2, 2), (3, 3), (17, 17)] {
+            let (sut, _) = GnuHashBuilder::new(ElfClass::Elf64).build(&names_of(count));
+            assert_eq!(sut.buckets.len(), buckets);
+        }
+        let (sut, order) = GnuHashBuilder::new(ElfClass::Elf64).build(&names_of(0));
+        assert!(order.is_empty());
+        let mut empty = vec![1u8, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0];
+        empty.extend_from_slice(&[0u8; 4 + 8 + 4]);
+        assert_eq!(sut.to_bytes(Endian::Little), empty);
+        assert!(GnuHash::parse(&[0u8; 16], ElfClass::Elf64, Endian::Little).is_err());
+    }
+}
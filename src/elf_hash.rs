@@ -0,0 +1,214 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{Checkpoint, CheckpointError, Resumable};
+use super::HasherU32;
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements the PJW based hash from the System V ABI used by ELF `.hash`
+/// sections.
+///
+/// It is not one of the DJB hashes but it is the other hash found in ELF
+/// symbol tables, next to the X33aU32 used by `.gnu.hash` sections. The
+/// result only ever has 28 bits set.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::elf_hash::*;
+/// let input = "printf";
+/// let mut hasher = ElfHash::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish(), 125371814u64);
+/// assert_eq!(hasher.finish_u32(), 125371814u32);
+/// ```
+///
+/// Unlike X33aU32 the strings "Ez" and "FY" do not collide but there are
+/// plenty of others that do, as only the low 4 bits of each byte are shifted
+/// in past the first few bytes.
+///
+/// ```rust
+/// # use std::hash::Hasher;
+/// # use djb_hash::elf_hash::*;
+/// let mut hasher1 = ElfHash::new();
+/// let mut hasher2 = ElfHash::new();
+/// hasher1.write(&"Ez".as_bytes());
+/// hasher2.write(&"FY".as_bytes());
+/// assert_eq!(hasher1.finish(), 1226u64);
+/// assert_eq!(hasher2.finish(), 1209u64);
+/// ```
+///
+pub struct ElfHash {
+    hash: u32,
+    salt: u32,
+    consumed: u64,
+    int_encoding: IntEncoding,
+}
+
+impl ElfHash {
+    ///
+    /// Creates a new hash starting from zero like the System V ABI does.
+    ///
+    pub fn new() -> Self {
+        ElfHash { hash: 0, salt: 0, consumed: 0, int_encoding: IntEncoding::LittleEndian }
+    }
+    ///
+    /// Creates a new hash using user supplied salt value.
+    ///
+    /// Only the low 28 bits of the salt are kept since the high 4 bits are
+    /// always cleared after each byte.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::hash::Hasher;
+    /// use djb_hash::HasherU32;
+    /// use djb_hash::elf_hash::*;
+    /// let mut hasher = ElfHash::new_with_salt(0);
+    /// hasher.write(&"printf".as_bytes());
+    /// assert_eq!(hasher.finish_u32(), 125371814u32);
+    /// ```
+    ///
+    pub fn new_with_salt(s: u32) -> Self {
+        ElfHash { hash: s & 0x0fff_ffff, salt: s & 0x0fff_ffff, consumed: 0, int_encoding: IntEncoding::LittleEndian }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for ElfHash {
+    ///
+    /// Same as new(), starting from zero.
+    ///
+    fn default() -> Self {
+        ElfHash::new()
+    }
+}
+
+impl HasherU32 for ElfHash {
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use std::hash::Hasher;
+    /// # use djb_hash::HasherU32;
+    /// # use djb_hash::elf_hash::*;
+    /// let mut hasher = ElfHash::new();
+    /// hasher.write(&"abcdefghijklmnopqrstuvwxyz".as_bytes());
+    /// assert_eq!(hasher.finish_u32(), 147972106u32);
+    /// ```
+    ///
+    fn finish_u32(&self) -> u32 {
+        self.hash
+    }
+}
+
+impl WriteIdentity for ElfHash {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 4).wrapping_add(value as u32);
+        let high = self.hash & 0xf000_0000;
+        if high != 0 {
+            self.hash ^= high >> 24;
+        }
+        self.hash &= !high;
+    }
+}
+
+impl Hasher for ElfHash {
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does hash << 4 + byte and then folds the high 4 bits back into bits 4
+    /// to 7 before clearing them.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        for byte in bytes {
+            self.hash = (self.hash << 4).wrapping_add(*byte as u32);
+            let high = self.hash & 0xf000_0000;
+            if high != 0 {
+                self.hash ^= high >> 24;
+            }
+            self.hash &= !high;
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for ElfHash {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(Algorithm::ElfHash, self.salt as u64, self.hash as u64, self.consumed, self.int_encoding)
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        checkpoint.expect(Algorithm::ElfHash, 0)?;
+        Ok(ElfHash {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            consumed: checkpoint.consumed,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let mut sut = ElfHash::new();
+        sut.write(&[]);
+        assert_eq!(sut.finish(), 0u64);
+        let input = "freelocale";
+        let mut sut = ElfHash::new();
+        sut.write(input.as_bytes());
+        assert_eq!(sut.finish(), 204689557u64);
+        assert_eq!(sut.finish_u32(), 204689557u32);
+        let mut sut = ElfHash::new_with_salt(0xffff_ffff);
+        sut.write(&[]);
+        assert_eq!(sut.finish_u32(), 0x0fff_ffff);
+    }
+}
@@ -0,0 +1,242 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Parser and builder for the System V `.hash` ELF symbol hash table section.
+//!
+//! This is the older `DT_HASH` table that still shows up in plenty of
+//! binaries next to, or instead of, a `.gnu.hash` section. It uses the PJW
+//! based ElfHash and is made up of the bucket and chain counts followed by the
+//! two arrays:
+//!
+//! ```text
+//! nbucket, nchain: u32
+//! buckets: [u32; nbucket]
+//! chains: [u32; nchain]
+//! ```
+//!
+//! There is one chain entry for every dynamic symbol so, unlike `.gnu.hash`,
+//! the symbols can be in any order. The 64 bit entries used by a few targets
+//! like Alpha and s390x are not supported.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::elf::*;
+//! use djb_hash::elf_sysv_hash::*;
+//! let bytes = include_bytes!("../tests/fixtures/elf/libdjb64-sysv.so");
+//! let elf = Elf::parse(&bytes[..]).unwrap();
+//! let symbols = elf.dynamic_symbols().unwrap();
+//! let table = SysvHash::from_elf(&elf).unwrap();
+//! let index = table.lookup(b"djb_alpha", &symbols).unwrap();
+//! assert_eq!(symbols[index as usize], &b"djb_alpha"[..]);
+//! assert_eq!(table.lookup(b"djb_omega", &symbols), None);
+//! ```
+//!
+use elf::{bucket_count, Elf, ElfError, Endian, SHT_HASH};
+use elf_hash::ElfHash;
+use std::hash::Hasher;
+use HasherU32;
+
+///
+/// Returns the System V ELF hash of the symbol name.
+///
+pub fn sysv_hash(name: &[u8]) -> u32 {
+    let mut hasher = ElfHash::new();
+    hasher.write(name);
+    hasher.finish_u32()
+}
+
+///
+/// A parsed or built `.hash` section.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SysvHash {
+    pub buckets: Vec<u32>,
+    pub chains: Vec<u32>,
+}
+
+impl SysvHash {
+    ///
+    /// Parses the contents of a `.hash` section.
+    ///
+    pub fn parse(section: &[u8], endian: Endian) -> Result<Self, ElfError> {
+        let nbucket = endian.read_u32(section, 0)? as usize;
+        let nchain = endian.read_u32(section, 4)? as usize;
+        if nbucket == 0 {
+            return Err(ElfError::Malformed("hash bucket count"));
+        }
+        if (section.len() - 8) / 4 < nbucket.saturating_add(nchain) {
+            return Err(ElfError::Truncated);
+        }
+        let mut words = section[8..].chunks(4).map(|w| endian.read_u32(w, 0));
+        let buckets = words.by_ref().take(nbucket).collect::<Result<_, _>>()?;
+        let chains = words.take(nchain).collect::<Result<_, _>>()?;
+        Ok(SysvHash { buckets, chains })
+    }
+    ///
+    /// Finds and parses the `.hash` section of an ELF file.
+    ///
+    pub fn from_elf(elf: &Elf) -> Result<Self, ElfError> {
+        let section = elf
+            .section_by_type(SHT_HASH)
+            .ok_or(ElfError::MissingSection(".hash"))?;
+        SysvHash::parse(section.data, elf.endian())
+    }
+    ///
+    /// Returns the dynamic symbol index of the name.
+    ///
+    /// `symbols` are the names of the dynamic symbols indexed by symbol number
+    /// as returned by `Elf::dynamic_symbols()`.
+    ///
+    pub fn lookup<S: AsRef<[u8]>>(&self, name: &[u8], symbols: &[S]) -> Option<u32> {
+        let hash = sysv_hash(name);
+        let mut index = *self.buckets.get((hash as usize) % self.buckets.len())?;
+        // A chain can never be longer than the number of symbols, anything
+        // more means the table loops.
+        for _ in 0..self.chains.len() {
+            if index == 0 {
+                return None;
+            }
+            if symbols.get(index as usize)?.as_ref() == name {
+                return Some(index);
+            }
+            index = *self.chains.get(index as usize)?;
+        }
+        None
+    }
+    ///
+    /// Returns the contents of the section in the given byte order.
+    ///
+    pub fn to_bytes(&self, endian: Endian) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + (self.buckets.len() + self.chains.len()) * 4);
+        endian.write_u32(&mut out, self.buckets.len() as u32);
+        endian.write_u32(&mut out, self.chains.len() as u32);
+        for &word in self.buckets.iter().chain(&self.chains) {
+            endian.write_u32(&mut out, word);
+        }
+        out
+    }
+}
+
+///
+/// Builds `.hash` sections from symbol names.
+///
+/// By default the table is sized the same way GNU ld sizes it.
+///
+#[derive(Clone, Debug, Default)]
+pub struct SysvHashBuilder {
+    buckets: Option<u32>,
+}
+
+impl SysvHashBuilder {
+    ///
+    /// Creates a new builder.
+    ///
+    pub fn new() -> Self {
+        SysvHashBuilder { buckets: None }
+    }
+    ///
+    /// Sets the number of buckets instead of using the one GNU ld would pick.
+    ///
+    pub fn buckets(mut self, buckets: u32) -> Self {
+        self.buckets = Some(buckets.max(1));
+        self
+    }
+    ///
+    /// Builds the section for every dynamic symbol name, in symbol order.
+    ///
+    /// The first name is the null symbol and is never put in a bucket.
+    ///
+    pub fn build<S: AsRef<[u8]>>(&self, symbols: &[S]) -> SysvHash {
+        // GNU ld sizes the table by the hashed symbols, not the null one.
+        let count = (symbols.len() as u32).saturating_sub(1);
+        let nbucket = self.buckets.unwrap_or_else(|| bucket_count(count, false));
+        let mut buckets = vec![0u32; nbucket as usize];
+        let mut chains = vec![0u32; symbols.len()];
+        for (index, name) in symbols.iter().enumerate().skip(1) {
+            let bucket = (sysv_hash(name.as_ref()) % nbucket) as usize;
+            chains[index] = buckets[bucket];
+            buckets[bucket] = index as u32;
+        }
+        SysvHash { buckets, chains }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ELF32: &[u8] = include_bytes!("../tests/fixtures/elf/libdjb32-sysv.so");
+    const ELF64: &[u8] = include_bytes!("../tests/fixtures/elf/libdjb64-sysv.so");
+    const ELF64_2: &[u8] = include_bytes!("../tests/fixtures/elf/libdjb64-sysv2.so");
+    const ELF64_16: &[u8] = include_bytes!("../tests/fixtures/elf/libdjb64-sysv16.so");
+
+    #[test]
+    fn it_does_lookup_and_build_correctly() {
+        assert_eq!(sysv_hash(b"printf"), 125_371_814);
+        for &data in &[ELF32, ELF64] {
+            let elf = Elf::parse(data).unwrap();
+            let section = elf.section(b".hash").unwrap().data;
+            let symbols = elf.dynamic_symbols().unwrap();
+            let sut = SysvHash::from_elf(&elf).unwrap();
+            assert_eq!(sut.buckets.len(), 17);
+            assert_eq!(sut.chains.len(), 31);
+            for (i, name) in symbols.iter().enumerate().skip(1) {
+                assert_eq!(sut.lookup(name, &symbols), Some(i as u32));
+            }
+            assert_eq!(sut.lookup(b"djb_omega", &symbols), None);
+            assert_eq!(sut.to_bytes(Endian::Little), section);
+            // The fixtures were linked by GNU ld so building from the same
+            // names has to give back the same section.
+            let built = SysvHashBuilder::new().build(&symbols);
+            assert_eq!(built, sut);
+            let big = built.to_bytes(Endian::Big);
+            assert_eq!(SysvHash::parse(&big, Endian::Big).unwrap(), sut);
+        }
+        // GNU ld picks 1 bucket for up to 2 hashed symbols and 3 for up to 16.
+        for &(data, buckets) in &[(ELF64_2, 1), (ELF64_16, 3)] {
+            let elf = Elf::parse(data).unwrap();
+            let symbols = elf.dynamic_symbols().unwrap();
+            let sut = SysvHash::from_elf(&elf).unwrap();
+            assert_eq!(sut.buckets.len(), buckets);
+            assert_eq!(SysvHashBuilder::new().build(&symbols), sut);
+        }
+        let symbols = ["", "alpha", "beta", "gamma", "delta", "epsilon"];
+        let sut = SysvHashBuilder::new().buckets(2).build(&symbols);
+        for (i, name) in symbols.iter().enumerate().skip(1) {
+            assert_eq!(sut.lookup(name.as_bytes(), &symbols), Some(i as u32));
+        }
+        let looped = SysvHash {
+            buckets: vec![1],
+            chains: vec![0, 1],
+        };
+        assert_eq!(looped.lookup(b"missing", &["", "other"]), None);
+        assert!(SysvHash::parse(&[1, 0, 0, 0, 9, 0, 0, 0], Endian::Little).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Hashing strings in the code units other languages use.
+//!
+//! Every hasher's write() takes bytes, which for a Rust `str` means UTF-8.
+//! Other systems hash the same text as something else: JS and Java loop over
+//! UTF-16 code units, old PHP often had Latin-1 bytes and Python 3 era tools
+//! work with Unicode code points. `Encoder` turns a `&str`, or any iterator of
+//! `char`s, into the chosen code units and feeds them to a hasher either as
+//! one wide step per unit, using `HasherUnit::write_unit()`, or as the bytes
+//! of each unit in a chosen byte order. The signed X33 variants only sign
+//! extend bytes; a wide unit is always added as its unsigned value, as a
+//! Java `char` is.
+//!
+//! UTF-8, UTF-16 and UTF-32 can encode every `char`. Only Latin-1 has
+//! characters it can not encode, everything past U+00FF, and what happens to
+//! them is set with `Unencodable`. By default they are an error and nothing
+//! past the last good character is written.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::HasherU32;
+//! use djb_hash::encoding::*;
+//! use djb_hash::x33a_u32::X33aU32;
+//! // The same as a JS loop doing hash * 33 + str.charCodeAt(i).
+//! let mut hasher = X33aU32::new();
+//! Encoder::new(Encoding::Utf16).write_str(&mut hasher, "héllo 日本 😀").unwrap();
+//! assert_eq!(hasher.finish_u32(), 2930222731);
+//! ```
+//!
+use std::error::Error;
+use std::fmt;
+use HasherUnit;
+
+///
+/// The code units a string is turned into.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    ///
+    /// UTF-8 bytes, the same as writing the `str` directly.
+    ///
+    Utf8,
+    ///
+    /// UTF-16 code units, with characters past U+FFFF as surrogate pairs.
+    ///
+    Utf16,
+    ///
+    /// UTF-32 code units, which are the Unicode code points themselves.
+    ///
+    Utf32,
+    ///
+    /// ISO 8859-1 bytes, which can only hold U+0000 to U+00FF.
+    ///
+    Latin1,
+}
+
+///
+/// How each code unit goes into the hasher.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnitMode {
+    ///
+    /// As a single value with `write_unit()`.
+    ///
+    Wide,
+    ///
+    /// As its bytes, least significant first.
+    ///
+    LittleEndian,
+    ///
+    /// As its bytes, most significant first.
+    ///
+    BigEndian,
+}
+
+///
+/// What to do with characters the encoding can not hold.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Unencodable {
+    ///
+    /// Stop and return an `EncodeError`.
+    ///
+    Error,
+    ///
+    /// Write this code unit instead, like the `?` PHP's `utf8_decode()` uses.
+    ///
+    Replace(u32),
+    ///
+    /// Leave the character out.
+    ///
+    Skip,
+}
+
+///
+/// A character the encoding can not hold.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EncodeError {
+    ///
+    /// The character.
+    ///
+    pub character: char,
+    ///
+    /// The number of characters before it.
+    ///
+    pub position: usize,
+    ///
+    /// The encoding it could not be put in.
+    ///
+    pub encoding: Encoding,
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "character U+{:04X} at position {} can not be encoded as {:?}",
+            self.character as u32, self.position, self.encoding
+        )
+    }
+}
+
+impl Error for EncodeError {}
+
+///
+/// Feeds strings to a hasher as the code units of an encoding.
+///
+/// Defaults to whole units with `UnitMode::Wide` and an error for characters
+/// the encoding can not hold. For UTF-8 and Latin-1 the unit is a byte so all
+/// three modes give the same hash.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Encoder {
+    encoding: Encoding,
+    mode: UnitMode,
+    unencodable: Unencodable,
+}
+
+impl Encoder {
+    ///
+    /// Creates a new encoder for the encoding.
+    ///
+    pub fn new(encoding: Encoding) -> Self {
+        Encoder {
+            encoding,
+            mode: UnitMode::Wide,
+            unencodable: Unencodable::Error,
+        }
+    }
+    ///
+    /// Sets how each code unit goes into the hasher.
+    ///
+    pub fn mode(mut self, mode: UnitMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    ///
+    /// Sets what to do with characters the encoding can not hold.
+    ///
+    pub fn unencodable(mut self, unencodable: Unencodable) -> Self {
+        self.unencodable = unencodable;
+        self
+    }
+    ///
+    /// Writes the code units of a string to the hasher.
+    ///
+    pub fn write_str<H: HasherUnit>(&self, hasher: &mut H, s: &str) -> Result<(), EncodeError> {
+        self.write_chars(hasher, s.chars())
+    }
+    ///
+    /// Writes the code units of the characters to the hasher.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::hash::Hasher;
+    /// use djb_hash::encoding::*;
+    /// use djb_hash::x33a::X33a;
+    /// let sut = Encoder::new(Encoding::Latin1).unencodable(Unencodable::Replace(b'?' as u32));
+    /// let mut hasher1 = X33a::new();
+    /// let mut hasher2 = X33a::new();
+    /// sut.write_chars(&mut hasher1, "€5".chars()).unwrap();
+    /// hasher2.write(b"?5");
+    /// assert_eq!(hasher1.finish(), hasher2.finish());
+    /// ```
+    ///
+    pub fn write_chars<H, I>(&self, hasher: &mut H, chars: I) -> Result<(), EncodeError>
+    where
+        H: HasherUnit,
+        I: IntoIterator<Item = char>,
+    {
+        let mut buffer = [0u16; 2];
+        for (position, character) in chars.into_iter().enumerate() {
+            match self.encoding {
+                Encoding::Utf8 => {
+                    let mut bytes = [0u8; 4];
+                    hasher.write(character.encode_utf8(&mut bytes).as_bytes());
+                }
+                Encoding::Utf16 => {
+                    for &unit in character.encode_utf16(&mut buffer).iter() {
+                        self.unit(hasher, unit as u32, 2);
+                    }
+                }
+                Encoding::Utf32 => self.unit(hasher, character as u32, 4),
+                Encoding::Latin1 => match (character as u32, self.unencodable) {
+                    (unit @ 0..=0xff, _) => hasher.write(&[unit as u8]),
+                    (_, Unencodable::Replace(unit)) => self.unit(hasher, unit, 1),
+                    (_, Unencodable::Skip) => {}
+                    (_, Unencodable::Error) => {
+                        return Err(EncodeError {
+                            character,
+                            position,
+                            encoding: self.encoding,
+                        })
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+    fn unit<H: HasherUnit>(&self, hasher: &mut H, unit: u32, size: usize) {
+        match self.mode {
+            UnitMode::Wide => hasher.write_unit(unit),
+            UnitMode::LittleEndian => hasher.write(&unit.to_le_bytes()[..size]),
+            UnitMode::BigEndian => hasher.write(&unit.to_be_bytes()[4 - size..]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::Hasher;
+    use x33a_u32::X33aU32;
+    use x33x::X33x;
+    use HasherU32;
+
+    fn hash(encoder: Encoder, s: &str) -> Result<u32, EncodeError> {
+        let mut hasher = X33aU32::new();
+        encoder.write_str(&mut hasher, s)?;
+        Ok(hasher.finish_u32())
+    }
+
+    #[test]
+    fn it_does_encode_correctly() {
+        let text = "héllo 日本 😀";
+        let vectors = [
+            (Encoding::Utf8, UnitMode::Wide, 1_944_846_935),
+            (Encoding::Utf16, UnitMode::Wide, 2_930_222_731),
+            (Encoding::Utf16, UnitMode::LittleEndian, 2_367_033_197),
+            (Encoding::Utf16, UnitMode::BigEndian, 917_390_061),
+            (Encoding::Utf32, UnitMode::Wide, 1_130_070_254),
+            (Encoding::Utf32, UnitMode::LittleEndian, 461_382_961),
+            (Encoding::Utf32, UnitMode::BigEndian, 1_865_258_417),
+        ];
+        for &(encoding, mode, expected) in vectors.iter() {
+            let sut = Encoder::new(encoding).mode(mode);
+            assert_eq!(hash(sut, text), Ok(expected));
+        }
+        let mut hasher = X33x::new();
+        Encoder::new(Encoding::Utf16)
+            .write_chars(&mut hasher, text.chars())
+            .unwrap();
+        assert_eq!(hasher.finish(), 13_775_125_267_227_826_239);
+        let latin1 = Encoder::new(Encoding::Latin1);
+        assert_eq!(hash(latin1, "café ü"), Ok(4_125_415_124));
+        assert_eq!(
+            hash(latin1, "naïve €5"),
+            Err(EncodeError {
+                character: '€',
+                position: 6,
+                encoding: Encoding::Latin1,
+            })
+        );
+        let replace = latin1.unencodable(Unencodable::Replace(b'?' as u32));
+        assert_eq!(hash(replace, "naïve €5"), Ok(1_625_158_738));
+        let skip = latin1.unencodable(Unencodable::Skip);
+        assert_eq!(hash(skip, "naïve €5"), Ok(179_397_747));
+    }
+}
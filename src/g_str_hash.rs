@@ -0,0 +1,193 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{flag, Checkpoint, CheckpointError, Resumable};
+use super::HasherU32;
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements GLib's `g_str_hash()`, the 32 bit times 33 hash over signed C `char` values that
+/// stops at the first NUL byte.
+///
+/// This is X33aU32Signed with C string handling added. Once a NUL byte is written the hash is done
+/// and any later bytes, in the same or later writes, are ignored just like they could never be seen
+/// by the C version.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::g_str_hash::*;
+/// let input = "日本";
+/// let mut hasher = GStrHash::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish_u32(), 3281679669u32);
+/// ```
+///
+/// Everything after a NUL is ignored:
+///
+/// ```rust
+/// # use std::hash::Hasher;
+/// # use djb_hash::HasherU32;
+/// # use djb_hash::g_str_hash::*;
+/// let mut hasher1 = GStrHash::new();
+/// let mut hasher2 = GStrHash::new();
+/// hasher1.write(&"abc\0def".as_bytes());
+/// hasher2.write(&"abc".as_bytes());
+/// assert_eq!(hasher1.finish_u32(), hasher2.finish_u32());
+/// ```
+///
+pub struct GStrHash {
+    hash: u32,
+    salt: u32,
+    consumed: u64,
+    ended: bool,
+    int_encoding: IntEncoding,
+}
+
+impl GStrHash {
+    ///
+    /// Creates a new hash using the 5381 starting value GLib uses.
+    ///
+    pub fn new() -> Self {
+        GStrHash {
+            hash: 5381,
+            salt: 5381,
+            consumed: 0,
+            ended: false,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for GStrHash {
+    ///
+    /// Same as new(), using the 5381 starting value.
+    ///
+    fn default() -> Self {
+        GStrHash::new()
+    }
+}
+
+impl HasherU32 for GStrHash {
+    fn finish_u32(&self) -> u32 {
+        self.hash
+    }
+}
+
+impl WriteIdentity for GStrHash {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        if !self.ended {
+            self.hash = (self.hash << 5).wrapping_add(self.hash).wrapping_add(value as u32);
+        }
+    }
+}
+
+impl Hasher for GStrHash {
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does hash * 33 + byte with the byte sign extended first, until the first NUL byte.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        if self.ended {
+            return;
+        }
+        for byte in bytes {
+            if *byte == 0 {
+                self.ended = true;
+                return;
+            }
+            self.hash = (self.hash << 5).wrapping_add(self.hash).wrapping_add(*byte as i8 as u32);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for GStrHash {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            extra: vec![self.ended as u8],
+            ..Checkpoint::new(Algorithm::GStrHash, self.salt as u64, self.hash as u64, self.consumed, self.int_encoding)
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        let extra = checkpoint.expect(Algorithm::GStrHash, 1)?;
+        Ok(GStrHash {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            consumed: checkpoint.consumed,
+            ended: flag(extra[0])?,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let vectors: [(&str, u32); 5] = [
+            ("", 5381),
+            ("Ez", 5862308),
+            ("é", 5857809),
+            ("naïve", 254740161),
+            ("Grüße", 1654414980),
+        ];
+        for &(input, expected) in vectors.iter() {
+            let mut sut = GStrHash::new();
+            sut.write(input.as_bytes());
+            assert_eq!(sut.finish_u32(), expected);
+        }
+        let mut sut = GStrHash::new();
+        sut.write(b"abc\0");
+        sut.write(b"def");
+        assert_eq!(sut.finish_u32(), 193485963u32);
+    }
+}
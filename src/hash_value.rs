@@ -0,0 +1,403 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Hash values that remember where they came from.
+//!
+//! finish() gives a bare `u64`, and once hashes of different algorithms and
+//! salts end up in the same log or table there is no telling them apart. A
+//! `HashValue` keeps the `Algorithm` and salt with the value and has a text
+//! form, `x33a_u32_php:80000a3f`, that can be stored and parsed back. The
+//! value is written in lowercase hex padded to the width of the algorithm. A
+//! salt other than the algorithm's default_salt() is added after the name as
+//! `@` and the salt in decimal, like `x33a@7919:000000000083a00e`.
+//!
+//! It also gives the value the way other languages print it, as a signed
+//! `int` in Java or an `int` in PHP, and as bytes, hex or base 36.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::{Algorithm, HashValue};
+//! let value = Algorithm::X33aU32Php.hash_value(5381, b"Ez");
+//! assert_eq!(value.to_string(), "x33a_u32_php:805973a4");
+//! assert_eq!(value.to_i32(), -2141621340);
+//! assert_eq!("x33a_u32_php:805973a4".parse::<HashValue>(), Ok(value));
+//! ```
+//!
+#[cfg(feature = "serde")]
+use serde::de::{self, Deserialize, Deserializer};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use Algorithm;
+
+///
+/// A hash value tagged with the algorithm and salt that made it.
+///
+/// Ordering is by algorithm, then salt, then value.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HashValue {
+    algorithm: Algorithm,
+    salt: u64,
+    value: u64,
+}
+
+impl HashValue {
+    ///
+    /// Creates a new tagged value.
+    ///
+    /// For the 32 bit algorithms only the lower 32 bits of the value are kept.
+    /// Algorithms that have no salt, see `Algorithm::takes_salt()`, always
+    /// record their default salt since any other would have had no effect.
+    ///
+    pub fn new(algorithm: Algorithm, salt: u64, value: u64) -> Self {
+        let salt = if algorithm.takes_salt() {
+            salt
+        } else {
+            algorithm.default_salt()
+        };
+        HashValue {
+            algorithm,
+            salt,
+            value: value & mask(algorithm),
+        }
+    }
+    ///
+    /// Returns the algorithm that made the value.
+    ///
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+    ///
+    /// Returns the salt the algorithm started from.
+    ///
+    pub fn salt(&self) -> u64 {
+        self.salt
+    }
+    ///
+    /// Returns the value the same as finish() gave it.
+    ///
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+    ///
+    /// Returns the value as a Java `int`, or PHP on a 32 bit build, prints it.
+    ///
+    /// For 64 bit algorithms this is the lower 32 bits, the same as an
+    /// `(int)` cast in Java.
+    ///
+    pub fn to_i32(&self) -> i32 {
+        self.value as u32 as i32
+    }
+    ///
+    /// Returns the value as a Java `long`, or PHP on a 64 bit build, prints it.
+    ///
+    /// The value is signed in the width of the algorithm, so a 32 bit value
+    /// is sign extended the same as a Java `int` widened to a `long`.
+    ///
+    pub fn to_i64(&self) -> i64 {
+        match self.algorithm.bits() {
+            32 => self.value as u32 as i32 as i64,
+            _ => self.value as i64,
+        }
+    }
+    ///
+    /// Returns the bytes of the value, most significant first, 4 of them for
+    /// 32 bit algorithms and 8 for 64 bit ones.
+    ///
+    pub fn to_be_bytes(&self) -> Vec<u8> {
+        let bytes = (self.algorithm.bits() / 8) as usize;
+        self.value.to_be_bytes()[8 - bytes..].to_vec()
+    }
+    ///
+    /// Returns the value in lowercase hex padded to the width of the
+    /// algorithm.
+    ///
+    pub fn to_hex(&self) -> String {
+        format!(
+            "{:0width$x}",
+            self.value,
+            width = (self.algorithm.bits() / 4) as usize
+        )
+    }
+    ///
+    /// Returns the value in lowercase base 36, like PHP's
+    /// `base_convert($hash, 10, 36)` or Java's `Long.toUnsignedString(hash, 36)`.
+    ///
+    pub fn to_base36(&self) -> String {
+        const DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+        let mut value = self.value;
+        let mut digits = Vec::new();
+        loop {
+            digits.push(DIGITS[(value % 36) as usize]);
+            value /= 36;
+            if value == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+        String::from_utf8(digits).unwrap()
+    }
+}
+
+fn mask(algorithm: Algorithm) -> u64 {
+    u64::MAX >> (64 - algorithm.bits())
+}
+
+impl From<HashValue> for u64 {
+    fn from(value: HashValue) -> Self {
+        value.value
+    }
+}
+
+impl fmt::Display for HashValue {
+    ///
+    /// Writes the value as `algorithm:hex`, with `@salt` after the algorithm
+    /// when it is not the default salt.
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.algorithm.name())?;
+        if self.salt != self.algorithm.default_salt() {
+            write!(f, "@{}", self.salt)?;
+        }
+        write!(f, ":{}", self.to_hex())
+    }
+}
+
+impl FromStr for HashValue {
+    type Err = ParseHashValueError;
+    ///
+    /// Parses the form Display writes. Upper case hex and hex without the
+    /// leading zeros are accepted too.
+    ///
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let colon = s.rfind(':').ok_or(ParseHashValueError::MissingValue)?;
+        let (tag, hex) = (&s[..colon], &s[colon + 1..]);
+        let (name, salt) = match tag.find('@') {
+            Some(at) => (&tag[..at], Some(&tag[at + 1..])),
+            None => (tag, None),
+        };
+        let algorithm = Algorithm::from_name(name)
+            .ok_or_else(|| ParseHashValueError::UnknownAlgorithm(name.to_string()))?;
+        let salt = match salt {
+            Some(salt) if salt.bytes().all(|b| b.is_ascii_digit()) => {
+                salt.parse().map_err(|_| ParseHashValueError::InvalidSalt)?
+            }
+            Some(_) => return Err(ParseHashValueError::InvalidSalt),
+            None => algorithm.default_salt(),
+        };
+        if !algorithm.takes_salt() && salt != algorithm.default_salt() {
+            return Err(ParseHashValueError::InvalidSalt);
+        }
+        if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseHashValueError::InvalidValue);
+        }
+        let value = u64::from_str_radix(hex, 16).map_err(|_| ParseHashValueError::OutOfRange)?;
+        if value & !mask(algorithm) != 0 {
+            return Err(ParseHashValueError::OutOfRange);
+        }
+        Ok(HashValue {
+            algorithm,
+            salt,
+            value,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for HashValue {
+    ///
+    /// Serializes the value as its text form.
+    ///
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for HashValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+///
+/// Why the text of a hash value could not be parsed.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseHashValueError {
+    ///
+    /// There is no `:` before the value.
+    ///
+    MissingValue,
+    ///
+    /// The name is not one of the algorithms. Holds the name.
+    ///
+    UnknownAlgorithm(String),
+    ///
+    /// The salt after the `@` is not a decimal `u64`, or the algorithm has no
+    /// salt.
+    ///
+    InvalidSalt,
+    ///
+    /// The value is empty or not hex.
+    ///
+    InvalidValue,
+    ///
+    /// The value does not fit the width of the algorithm.
+    ///
+    OutOfRange,
+}
+
+impl fmt::Display for ParseHashValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseHashValueError::MissingValue => f.write_str("missing ':' before the hash value"),
+            ParseHashValueError::UnknownAlgorithm(ref name) => {
+                write!(f, "unknown hash algorithm \"{}\"", name)
+            }
+            ParseHashValueError::InvalidSalt => f.write_str("salt is not a decimal number"),
+            ParseHashValueError::InvalidValue => f.write_str("hash value is not hex"),
+            ParseHashValueError::OutOfRange => {
+                f.write_str("hash value does not fit the width of the algorithm")
+            }
+        }
+    }
+}
+
+impl Error for ParseHashValueError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn it_does_format_correctly() {
+        let sut = HashValue::new(Algorithm::X33aU32Php, 5381, 0x8000_0a3f);
+        assert_eq!(sut.to_string(), "x33a_u32_php:80000a3f");
+        assert_eq!(sut.to_i32(), -2_147_481_025);
+        assert_eq!(sut.to_i64(), -2_147_481_025);
+        assert_eq!(sut.to_be_bytes(), vec![0x80, 0x00, 0x0a, 0x3f]);
+        assert_eq!(sut.to_hex(), "80000a3f");
+        assert_eq!(sut.to_base36(), "zik30f");
+        assert_eq!(u64::from(sut), 0x8000_0a3f);
+        let sut = Algorithm::X33aPhp.hash_value(7919, b"Ez");
+        assert_eq!(sut.to_string(), "x33a_php@7919:800000000083a00e");
+        assert_eq!(sut.to_i32(), 0x0083_a00e);
+        assert_eq!(sut.to_i64(), -9_223_372_036_846_149_618);
+        assert_eq!(sut.to_be_bytes().len(), 8);
+        assert_eq!(HashValue::new(Algorithm::X33x, 5381, 0).to_base36(), "0");
+        assert_eq!(HashValue::new(Algorithm::X33aU32, 5381, 1 << 40).value(), 0);
+        let sut = Algorithm::TclHash.hash_value(7919, b"Ez");
+        assert_eq!(sut.salt(), 0);
+        assert_eq!(sut, Algorithm::TclHash.hash_value(0, b"Ez"));
+    }
+
+    #[test]
+    fn it_does_parse_correctly() {
+        for s in [
+            "x33a_u32_php:80000a3f",
+            "x33a_php@7919:800000000083a00e",
+            "x33x@0:0000000000000000",
+        ]
+        .iter()
+        {
+            let sut: HashValue = s.parse().unwrap();
+            assert_eq!(sut.to_string(), *s);
+        }
+        assert_eq!(
+            "X33A_U32:A3F".parse::<HashValue>(),
+            Err(ParseHashValueError::UnknownAlgorithm(
+                "X33A_U32".to_string()
+            ))
+        );
+        assert_eq!(
+            "x33a_u32:A3F".parse(),
+            Ok(HashValue::new(Algorithm::X33aU32, 5381, 0xa3f))
+        );
+        assert_eq!(
+            "x33a_u32".parse::<HashValue>(),
+            Err(ParseHashValueError::MissingValue)
+        );
+        assert_eq!(
+            "x33a_u32@-1:a3f".parse::<HashValue>(),
+            Err(ParseHashValueError::InvalidSalt)
+        );
+        assert_eq!(
+            "tcl_hash@7919:a3f".parse::<HashValue>(),
+            Err(ParseHashValueError::InvalidSalt)
+        );
+        assert_eq!(
+            "x33a_u32:".parse::<HashValue>(),
+            Err(ParseHashValueError::InvalidValue)
+        );
+        assert_eq!(
+            "x33a_u32:+a3f".parse::<HashValue>(),
+            Err(ParseHashValueError::InvalidValue)
+        );
+        assert_eq!(
+            "x33a_u32:100000000".parse::<HashValue>(),
+            Err(ParseHashValueError::OutOfRange)
+        );
+        assert_eq!(
+            "x33a:10000000000000000".parse::<HashValue>(),
+            Err(ParseHashValueError::OutOfRange)
+        );
+        let set: BTreeSet<HashValue> = ["x33x:2", "x33a@1:1", "x33a:3", "x33a:1"]
+            .iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let sorted: Vec<String> = set.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            sorted,
+            vec![
+                "x33a@1:0000000000000001",
+                "x33a:0000000000000001",
+                "x33a:0000000000000003",
+                "x33x:0000000000000002",
+            ]
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn it_does_serde_correctly() {
+        let sut = HashValue::new(Algorithm::X33aU32Php, 5381, 0x8000_0a3f);
+        let json = serde_json::to_string(&sut).unwrap();
+        assert_eq!(json, r#""x33a_u32_php:80000a3f""#);
+        assert_eq!(serde_json::from_str::<HashValue>(&json).unwrap(), sut);
+        assert!(serde_json::from_str::<HashValue>(r#""x33:1""#).is_err());
+    }
+}
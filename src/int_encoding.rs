@@ -0,0 +1,352 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Fixed byte orders and widths for hashing integers.
+//!
+//! The default integer write_*() functions of `Hasher` write the native-endian
+//! bytes of the integer, and `usize` and `isize` in the native width, so
+//! hashing the same struct gives a different value on a 32 bit or big-endian
+//! target. Every hasher in the crate instead writes integers the way its
+//! `IntEncoding` says, which can be set with its int_encoding() function.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use djb_hash::IntEncoding;
+//! use djb_hash::x33a_u32::X33aU32;
+//! let mut hasher1 = X33aU32::new().int_encoding(IntEncoding::BigEndian);
+//! let mut hasher2 = X33aU32::new();
+//! hasher1.write_usize(0x4579);
+//! hasher2.write(&[0, 0, 0, 0, 0, 0, 0x45, 0x79]);
+//! assert_eq!(hasher1.finish(), hasher2.finish());
+//! ```
+//!
+use std::hash::Hasher;
+
+///
+/// How the integer write_*() functions of a hasher turn an integer into
+/// hash input.
+///
+/// In both byte orders `usize` and `isize` are always written as 8 bytes, the
+/// same as `u64` and `i64`, so a hash is the same on 32 and 64 bit targets.
+/// On a 64 bit little-endian target `IntEncoding::LittleEndian` gives the same
+/// hashes as the default functions in `Hasher` do.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IntEncoding {
+    ///
+    /// The bytes of the integer, least significant first. This is the default.
+    ///
+    LittleEndian,
+    ///
+    /// The bytes of the integer, most significant first.
+    ///
+    BigEndian,
+    ///
+    /// The integer's value as a single step of the hash, like a byte would be.
+    ///
+    /// This is how Java and PHP treat an integer: its hash is the value
+    /// itself, and Java's `Arrays.hashCode()` combines those values one step
+    /// each. Signed integers are sign extended. Integers wider than the hash
+    /// are folded by XOR of their two halves, like Java's `Long.hashCode()`,
+    /// until they fit.
+    ///
+    Language,
+}
+
+impl Default for IntEncoding {
+    ///
+    /// Same as `IntEncoding::LittleEndian`.
+    ///
+    fn default() -> Self {
+        IntEncoding::LittleEndian
+    }
+}
+
+///
+/// Hashers that can take an integer as a single step.
+///
+pub(crate) trait WriteIdentity: Hasher {
+    ///
+    /// The width of the hash, which integers are folded to.
+    ///
+    const BITS: u32;
+    ///
+    /// Adds the value to the hash in a single step.
+    ///
+    fn write_identity(&mut self, value: u64);
+}
+
+///
+/// Writes an integer of `size` bytes, sign extended to 128 bits if signed.
+///
+pub(crate) fn write_int<H: WriteIdentity>(
+    hasher: &mut H,
+    encoding: IntEncoding,
+    value: u128,
+    size: usize,
+) {
+    match encoding {
+        IntEncoding::LittleEndian => hasher.write(&value.to_le_bytes()[..size]),
+        IntEncoding::BigEndian => hasher.write(&value.to_be_bytes()[16 - size..]),
+        IntEncoding::Language => hasher.write_identity(identity(value, size, H::BITS)),
+    }
+}
+
+fn identity(value: u128, size: usize, bits: u32) -> u64 {
+    let mut value = value;
+    let mut width = size as u32 * 8;
+    while width > bits {
+        if width < 128 {
+            value &= (1 << width) - 1;
+        }
+        width /= 2;
+        value ^= value >> width;
+    }
+    if bits < 64 {
+        value as u64 & ((1 << bits) - 1)
+    } else {
+        value as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use apr::AprHash;
+    use elf_hash::ElfHash;
+    use encoding::{Encoder, Encoding};
+    use g_str_hash::GStrHash;
+    use libiberty_hash::LibibertyHash;
+    use lua_hash::{LuaHash, LuaVersion};
+    use nginx::{NgxHashKey, NgxHashKeyLc};
+    use perl_hash::PerlHash;
+    use py2_str_hash::Py2StrHash;
+    use py2_str_hash_u32::Py2StrHashU32;
+    use redis::RedisCaseHash;
+    use sdbm::Sdbm;
+    use tcl_hash::TclHash;
+    use x33a::X33a;
+    use x33a_php::X33aPhp;
+    use x33a_php_signed::X33aPhpSigned;
+    use x33a_signed::X33aSigned;
+    use x33a_u32::X33aU32;
+    use x33a_u32_php::X33aU32Php;
+    use x33a_u32_php_signed::X33aU32PhpSigned;
+    use x33a_u32_signed::X33aU32Signed;
+    use x33x::X33x;
+    use x33x_u32::X33xU32;
+
+    fn write_all<H: Hasher>(mut hasher: H) -> u64 {
+        hasher.write_u8(0x80);
+        hasher.write_i8(-2);
+        hasher.write_u16(0x4142);
+        hasher.write_i16(-3);
+        hasher.write_u32(0x4142_4344);
+        hasher.write_i32(-4);
+        hasher.write_u64(0x0102_0304_0506_0708);
+        hasher.write_i64(-5);
+        hasher.write_u128(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10);
+        hasher.write_i128(-6);
+        hasher.write_usize(0x4579);
+        hasher.write_isize(-7);
+        hasher.finish()
+    }
+
+    fn pins<H: Hasher, F: Fn(IntEncoding) -> H>(new: F) -> [u64; 3] {
+        [
+            write_all(new(IntEncoding::LittleEndian)),
+            write_all(new(IntEncoding::BigEndian)),
+            write_all(new(IntEncoding::Language)),
+        ]
+    }
+
+    #[test]
+    fn it_does_encode_correctly() {
+        assert_eq!(
+            pins(|e| X33a::new().int_encoding(e)),
+            [
+                7380225891560399552,
+                4740329817885622368,
+                16036018619682155316
+            ]
+        );
+        assert_eq!(
+            pins(|e| X33aPhp::new().int_encoding(e)),
+            [
+                16603597928415175360,
+                13963701854740398176,
+                16036018619682155316
+            ]
+        );
+        assert_eq!(
+            pins(|e| X33aPhpSigned::new().int_encoding(e)),
+            [
+                13532885926528481984,
+                10892989852853704800,
+                16036018619682155316
+            ]
+        );
+        assert_eq!(
+            pins(|e| X33aSigned::new().int_encoding(e)),
+            [
+                13532885926528481984,
+                10892989852853704800,
+                16036018619682155316
+            ]
+        );
+        assert_eq!(
+            pins(|e| X33aU32::new().int_encoding(e)),
+            [2761499328, 2705571936, 2256575558]
+        );
+        assert_eq!(
+            pins(|e| X33aU32Php::new().int_encoding(e)),
+            [2761499328, 2705571936, 2256575558]
+        );
+        assert_eq!(
+            pins(|e| X33aU32PhpSigned::new().int_encoding(e)),
+            [3298622144, 3242694752, 2256575558]
+        );
+        assert_eq!(
+            pins(|e| X33aU32Signed::new().int_encoding(e)),
+            [3298622144, 3242694752, 2256575558]
+        );
+        assert_eq!(
+            pins(|e| X33x::new().int_encoding(e)),
+            [
+                9395364547519087390,
+                8541370432585646718,
+                12324560133196742642
+            ]
+        );
+        assert_eq!(
+            pins(|e| X33xU32::new().int_encoding(e)),
+            [4130112286, 2169160318, 1728237758]
+        );
+        assert_eq!(
+            pins(|e| AprHash::new().int_encoding(e)),
+            [3536290043, 3480362651, 769513409]
+        );
+        assert_eq!(
+            pins(|e| ElfHash::new().int_encoding(e)),
+            [250909759, 198823081, 213379526]
+        );
+        assert_eq!(
+            pins(|e| GStrHash::new().int_encoding(e)),
+            [1483774734, 3683243152, 2256575558]
+        );
+        assert_eq!(
+            pins(|e| LibibertyHash::new().int_encoding(e)),
+            [4150993281, 15568091, 4223328151]
+        );
+        assert_eq!(
+            pins(|e| LuaHash::new(LuaVersion::Lua53).int_encoding(e)),
+            [2097001961, 3027297240, 1693210211]
+        );
+        assert_eq!(
+            pins(|e| NgxHashKey::new().int_encoding(e)),
+            [
+                5715075055569764111,
+                2488635124001648013,
+                16827958230818514673
+            ]
+        );
+        assert_eq!(
+            pins(|e| NgxHashKeyLc::new().int_encoding(e)),
+            [
+                1886404094995845935,
+                15476596020205444461,
+                16827958230818514673
+            ]
+        );
+        assert_eq!(
+            pins(|e| PerlHash::new().int_encoding(e)),
+            [3646799106, 3589123983, 793560703]
+        );
+        assert_eq!(
+            pins(|e| Py2StrHash::new().int_encoding(e)),
+            [
+                1911679699662922381,
+                4106638904888206811,
+                7068330798575746525
+            ]
+        );
+        assert_eq!(
+            pins(|e| Py2StrHashU32::new().int_encoding(e)),
+            [4005290637, 2057905627, 1550800601]
+        );
+        assert_eq!(
+            pins(|e| RedisCaseHash::new().int_encoding(e)),
+            [3136915360, 3490222400, 2256575558]
+        );
+        assert_eq!(
+            pins(|e| Sdbm::new().int_encoding(e)),
+            [1168858575, 80767597, 1870237867]
+        );
+        assert_eq!(
+            pins(|e| TclHash::new().int_encoding(e)),
+            [2021282041, 400621915, 2158612393]
+        );
+        let mut sut = X33aU32::new();
+        let mut expected = X33aU32::new();
+        sut.write_usize(0x4579);
+        expected.write(&[0x79, 0x45, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(sut.finish(), expected.finish());
+        // Latin-1 text is written as bytes, so it is sign extended the same
+        // whatever the integer encoding.
+        let latin1 = Encoder::new(Encoding::Latin1);
+        let mut sut = X33aSigned::new().int_encoding(IntEncoding::Language);
+        latin1.write_str(&mut sut, "é").unwrap();
+        assert_eq!(sut.finish(), 177550);
+        let mut sut = X33aU32Signed::new().int_encoding(IntEncoding::Language);
+        latin1.write_str(&mut sut, "é").unwrap();
+        let mut expected = X33aU32Signed::new();
+        expected.write(&[0xe9]);
+        assert_eq!(sut.finish(), expected.finish());
+    }
+
+    #[test]
+    fn it_does_identity_correctly() {
+        assert_eq!(identity(-1i32 as i128 as u128, 4, 32), 0xffff_ffff);
+        assert_eq!(identity(-1i64 as i128 as u128, 8, 32), 0);
+        assert_eq!(identity(-1i64 as i128 as u128, 8, 64), u64::MAX);
+        assert_eq!(identity(-1i8 as i128 as u128, 1, 64), u64::MAX);
+        assert_eq!(identity(0x1_0000_0002, 8, 32), 3);
+        assert_eq!(
+            identity(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10, 16, 64),
+            0x0808_0808_0808_0818
+        );
+        assert_eq!(
+            identity(0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10, 16, 32),
+            0x0000_0010
+        );
+    }
+}
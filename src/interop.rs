@@ -0,0 +1,268 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Map keys that hash as just their bytes.
+//!
+//! `Hash` for `str` and `String` writes the bytes followed by a 0xFF and
+//! `Hash` for `[u8]` and `Vec<u8>` writes the length first. Both are there so
+//! keys like ("ab", "c") and ("a", "bc") hash differently, but it means a
+//! `HashMap<String, V, BuildHasherDefault<X33aPhp>>` never gets the hash PHP
+//! would for the same key. `InteropKey` and `RawBytes` hash with one write()
+//! of exactly the key bytes, so the hash the map uses is the same value the
+//! hasher gives for the bytes on its own and the same value the other
+//! language uses.
+//!
+//! Only use them where that is the point. Composite keys made of them, like a
+//! tuple of two `InteropKey`s, lose the protection the terminator gave.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::collections::HashMap;
+//! use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+//! use djb_hash::interop::*;
+//! use djb_hash::x33a_php::X33aPhp;
+//! let mut map: HashMap<InteropKey<String>, u32, BuildHasherDefault<X33aPhp>> = HashMap::default();
+//! map.insert(InteropKey("Ez".to_string()), 1);
+//! assert_eq!(map.get(RawBytes::new("Ez")), Some(&1));
+//! let mut hasher = X33aPhp::new();
+//! hasher.write(b"Ez");
+//! let mut map_hasher = map.hasher().build_hasher();
+//! RawBytes::new("Ez").hash(&mut map_hasher);
+//! assert_eq!(map_hasher.finish(), hasher.finish());
+//! assert_eq!(hasher.finish(), 9223372036860638116u64);
+//! ```
+//!
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+///
+/// A map key that hashes as only its bytes.
+///
+/// Equality and ordering are also by the bytes, which keeps them the same as
+/// `RawBytes` so a map of `InteropKey`s can be searched with a `&RawBytes`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::{Hash, Hasher};
+/// use djb_hash::interop::InteropKey;
+/// use djb_hash::x33a::X33a;
+/// let mut hasher1 = X33a::new();
+/// let mut hasher2 = X33a::new();
+/// InteropKey("Ez").hash(&mut hasher1);
+/// hasher2.write(b"Ez");
+/// assert_eq!(hasher1.finish(), hasher2.finish());
+/// ```
+///
+#[derive(Clone, Copy, Default)]
+pub struct InteropKey<T>(pub T);
+
+impl<T> InteropKey<T> {
+    ///
+    /// Returns the wrapped key.
+    ///
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T: AsRef<[u8]>> InteropKey<T> {
+    ///
+    /// Returns the bytes that are hashed.
+    ///
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl<T> From<T> for InteropKey<T> {
+    fn from(key: T) -> Self {
+        InteropKey(key)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for InteropKey<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("InteropKey").field(&self.0).finish()
+    }
+}
+
+impl<T: AsRef<[u8]>> Hash for InteropKey<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(self.as_bytes());
+    }
+}
+
+impl<T: AsRef<[u8]>> PartialEq for InteropKey<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<T: AsRef<[u8]>> Eq for InteropKey<T> {}
+
+impl<T: AsRef<[u8]>> PartialOrd for InteropKey<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: AsRef<[u8]>> Ord for InteropKey<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_bytes().cmp(other.as_bytes())
+    }
+}
+
+impl<T: AsRef<[u8]>> Borrow<RawBytes> for InteropKey<T> {
+    fn borrow(&self) -> &RawBytes {
+        RawBytes::new(self.as_bytes())
+    }
+}
+
+///
+/// A borrowed byte string that hashes as only its bytes.
+///
+/// This is the unsized partner of `InteropKey`, the way `str` is to
+/// `String`, and is what map lookups take.
+///
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(transparent)]
+pub struct RawBytes([u8]);
+
+impl RawBytes {
+    ///
+    /// Wraps a `str`, byte slice or anything else that is bytes.
+    ///
+    pub fn new<B: AsRef<[u8]> + ?Sized>(bytes: &B) -> &RawBytes {
+        let bytes: &[u8] = bytes.as_ref();
+        // RawBytes is repr(transparent) over [u8] so the two have the same
+        // layout and pointer metadata.
+        unsafe { &*(bytes as *const [u8] as *const RawBytes) }
+    }
+    ///
+    /// Returns the bytes that are hashed.
+    ///
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for RawBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl<'a> From<&'a [u8]> for &'a RawBytes {
+    fn from(bytes: &'a [u8]) -> Self {
+        RawBytes::new(bytes)
+    }
+}
+
+impl<'a> From<&'a str> for &'a RawBytes {
+    fn from(s: &'a str) -> Self {
+        RawBytes::new(s)
+    }
+}
+
+impl Hash for RawBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::hash::{BuildHasher, BuildHasherDefault};
+    use x33a::X33a;
+    use x33a_php::X33aPhp;
+    use x33a_u32::X33aU32;
+    use x33x::X33x;
+
+    fn one_shot<H: Hasher + Default>(bytes: &[u8]) -> u64 {
+        let mut hasher = H::default();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    fn map_hash<H: Hasher + Default, K: Hash + ?Sized>(key: &K) -> u64 {
+        let mut hasher = BuildHasherDefault::<H>::default().build_hasher();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn it_does_hash_correctly() {
+        for key in ["", "Ez", "FY", "Content-Type", "日本"].iter() {
+            let bytes = key.as_bytes();
+            let expected = one_shot::<X33aPhp>(bytes);
+            assert_eq!(map_hash::<X33aPhp, _>(&InteropKey(*key)), expected);
+            assert_eq!(
+                map_hash::<X33aPhp, _>(&InteropKey(key.to_string())),
+                expected
+            );
+            assert_eq!(
+                map_hash::<X33aPhp, _>(&InteropKey(bytes.to_vec())),
+                expected
+            );
+            assert_eq!(map_hash::<X33aPhp, _>(RawBytes::new(key)), expected);
+            assert_ne!(map_hash::<X33aPhp, _>(*key), expected);
+            assert_eq!(
+                map_hash::<X33a, _>(RawBytes::new(bytes)),
+                one_shot::<X33a>(bytes)
+            );
+            assert_eq!(
+                map_hash::<X33x, _>(&InteropKey(*key)),
+                one_shot::<X33x>(bytes)
+            );
+            assert_eq!(
+                map_hash::<X33aU32, _>(&InteropKey(*key)),
+                one_shot::<X33aU32>(bytes)
+            );
+        }
+        assert_eq!(
+            map_hash::<X33aPhp, _>(RawBytes::new("Ez")),
+            9223372036860638116u64
+        );
+        let mut map: HashMap<InteropKey<Vec<u8>>, u32, BuildHasherDefault<X33aU32>> =
+            HashMap::default();
+        map.insert(InteropKey(b"Ez".to_vec()), 1);
+        map.insert(InteropKey(b"FY".to_vec()), 2);
+        assert_eq!(map.get(RawBytes::new("Ez")), Some(&1));
+        assert_eq!(map.get(RawBytes::new(b"FY")), Some(&2));
+        assert_eq!(map.get(RawBytes::new("Fy")), None);
+        assert!(InteropKey("a") < InteropKey("b"));
+        assert_eq!(InteropKey("Ez").as_bytes(), RawBytes::new("Ez").as_bytes());
+    }
+}
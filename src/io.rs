@@ -0,0 +1,483 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Hashing readers and writers as the bytes go through.
+//!
+//! `HashWriter` and `HashReader` wrap any `io::Write` or `io::Read` and hash
+//! every byte that goes through them, so a file can be hashed while it is
+//! copied and a network stream while it is parsed. hash_reader() hashes all
+//! of a reader in one go.
+//!
+//! For line or record based data, lines() and records() go through a
+//! `BufRead` and give the hash of each line or record along with where it
+//! starts, without keeping any of it in memory.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::io::{self, Write};
+//! use djb_hash::io::*;
+//! use djb_hash::x33a_u32::X33aU32;
+//! let mut writer = HashWriter::new(X33aU32::new(), Vec::new());
+//! writer.write_all(b"Ez").unwrap();
+//! assert_eq!(writer.finish(), 5862308);
+//! assert_eq!(writer.get_ref(), b"Ez");
+//! assert_eq!(hash_reader::<X33aU32, _>(&b"Ez"[..]).unwrap(), 5862308);
+//! ```
+//!
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, BufRead, Read, Write};
+
+///
+/// The buffer size hash_reader() reads with.
+///
+/// The hashes themselves go a byte at a time so the buffer is only there to
+/// keep the number of reads down. 64 KiB is a pipe's worth on Linux and well
+/// past the point where bigger buffers stop helping with files.
+///
+pub const BUFFER_SIZE: usize = 64 * 1024;
+
+///
+/// Hashes everything written before passing it on to the inner writer.
+///
+/// Only the bytes the inner writer took are hashed, so a short write never
+/// leaves the hash ahead of what was written. Use `io::sink()` as the inner
+/// writer to only hash.
+///
+pub struct HashWriter<H, W> {
+    hasher: H,
+    inner: W,
+}
+
+impl<H: Hasher, W: Write> HashWriter<H, W> {
+    ///
+    /// Creates a new writer hashing with the hasher.
+    ///
+    pub fn new(hasher: H, inner: W) -> Self {
+        HashWriter { hasher, inner }
+    }
+    ///
+    /// Returns the hash of everything written so far.
+    ///
+    pub fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+    ///
+    /// Returns a reference to the hasher.
+    ///
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+    ///
+    /// Returns a reference to the inner writer.
+    ///
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    ///
+    /// Returns a mutable reference to the inner writer.
+    ///
+    /// Anything written to it directly is not hashed.
+    ///
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+    ///
+    /// Returns the hasher and the inner writer.
+    ///
+    pub fn into_parts(self) -> (H, W) {
+        (self.hasher, self.inner)
+    }
+}
+
+impl<H: Hasher, W: Write> Write for HashWriter<H, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.write(&buf[..written]);
+        Ok(written)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+///
+/// Hashes everything read from the inner reader.
+///
+pub struct HashReader<H, R> {
+    hasher: H,
+    inner: R,
+}
+
+impl<H: Hasher, R: Read> HashReader<H, R> {
+    ///
+    /// Creates a new reader hashing with the hasher.
+    ///
+    pub fn new(hasher: H, inner: R) -> Self {
+        HashReader { hasher, inner }
+    }
+    ///
+    /// Returns the hash of everything read so far.
+    ///
+    pub fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+    ///
+    /// Returns a reference to the hasher.
+    ///
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+    ///
+    /// Returns a reference to the inner reader.
+    ///
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    ///
+    /// Returns a mutable reference to the inner reader.
+    ///
+    /// Anything read from it directly is not hashed.
+    ///
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+    ///
+    /// Returns the hasher and the inner reader.
+    ///
+    pub fn into_parts(self) -> (H, R) {
+        (self.hasher, self.inner)
+    }
+}
+
+impl<H: Hasher, R: Read> Read for HashReader<H, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.write(&buf[..read]);
+        Ok(read)
+    }
+}
+
+///
+/// Hashes everything left in the reader with a new `H`.
+///
+/// # Examples
+///
+/// ```rust
+/// use djb_hash::io::hash_reader;
+/// use djb_hash::x33a_php::X33aPhp;
+/// let hash = hash_reader::<X33aPhp, _>(&b"Ez"[..]).unwrap();
+/// assert_eq!(hash, 9223372036860638116);
+/// ```
+///
+pub fn hash_reader<H: Hasher + Default, R: Read>(reader: R) -> io::Result<u64> {
+    let mut hasher = H::default();
+    write_reader(&mut hasher, reader)?;
+    Ok(hasher.finish())
+}
+
+///
+/// Writes everything left in the reader to the hasher, returning the number
+/// of bytes read.
+///
+pub fn write_reader<H: Hasher, R: Read>(hasher: &mut H, mut reader: R) -> io::Result<u64> {
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut total = 0u64;
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(total),
+            Ok(read) => {
+                hasher.write(&buffer[..read]);
+                total += read as u64;
+            }
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+///
+/// The hash of one line or record.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Record {
+    ///
+    /// Where the record starts, counted in bytes from where reading began.
+    ///
+    pub offset: u64,
+    ///
+    /// The number of bytes hashed, which leaves out the line ending or
+    /// delimiter.
+    ///
+    pub len: u64,
+    ///
+    /// The hash of the record.
+    ///
+    pub hash: u64,
+}
+
+///
+/// An iterator over the hashes of the lines or records of a `BufRead`.
+///
+/// Created by lines() and records().
+///
+pub struct Records<B, S> {
+    reader: B,
+    build: S,
+    delimiter: u8,
+    lines: bool,
+    offset: u64,
+}
+
+///
+/// Returns an iterator over the hashes of the lines of the reader, using a
+/// new hasher from `build` for each.
+///
+/// Lines are split the same as `BufRead::lines()` does: the `\n` or `\r\n`
+/// ending is not hashed and a last line without an ending is still given.
+/// Unlike `lines()` the bytes do not need to be UTF-8.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::BuildHasherDefault;
+/// use djb_hash::io::*;
+/// use djb_hash::x33a_u32::X33aU32;
+/// let input = &b"Ez\r\nFY\n\nEz"[..];
+/// let build = BuildHasherDefault::<X33aU32>::default();
+/// let lines: Vec<Record> = lines(input, build).map(|r| r.unwrap()).collect();
+/// assert_eq!(lines.len(), 4);
+/// assert_eq!(lines[0], Record { offset: 0, len: 2, hash: 5862308 });
+/// assert_eq!(lines[1].hash, lines[0].hash);
+/// assert_eq!(lines[2], Record { offset: 7, len: 0, hash: 5381 });
+/// assert_eq!(lines[3].offset, 8);
+/// ```
+///
+pub fn lines<B: BufRead, S: BuildHasher>(reader: B, build: S) -> Records<B, S> {
+    Records {
+        reader,
+        build,
+        delimiter: b'\n',
+        lines: true,
+        offset: 0,
+    }
+}
+
+///
+/// Returns an iterator over the hashes of the records of the reader split on
+/// the delimiter, like `BufRead::split()`, using a new hasher from `build`
+/// for each.
+///
+/// The delimiter is not hashed and a last record without one is still given.
+///
+pub fn records<B: BufRead, S: BuildHasher>(reader: B, build: S, delimiter: u8) -> Records<B, S> {
+    Records {
+        reader,
+        build,
+        delimiter,
+        lines: false,
+        offset: 0,
+    }
+}
+
+impl<B: BufRead, S: BuildHasher> Records<B, S> {
+    ///
+    /// Returns a reference to the reader.
+    ///
+    pub fn get_ref(&self) -> &B {
+        &self.reader
+    }
+    ///
+    /// Returns the reader.
+    ///
+    pub fn into_inner(self) -> B {
+        self.reader
+    }
+}
+
+impl<B: BufRead, S: BuildHasher> Iterator for Records<B, S> {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<io::Result<Record>> {
+        let mut hasher = self.build.build_hasher();
+        let (delimiter, lines) = (self.delimiter, self.lines);
+        let offset = self.offset;
+        let mut len = 0u64;
+        // A `\r` at the end of a chunk that may yet turn out to be part of
+        // a `\r\n` line ending.
+        let mut carriage_return = false;
+        loop {
+            let (used, found) = {
+                let available = match self.reader.fill_buf() {
+                    Ok(available) => available,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Some(Err(e)),
+                };
+                if available.is_empty() {
+                    if self.offset == offset {
+                        return None;
+                    }
+                    if carriage_return {
+                        hasher.write(b"\r");
+                        len += 1;
+                    }
+                    break;
+                }
+                let found = available.iter().position(|&b| b == delimiter);
+                let mut data = &available[..found.unwrap_or(available.len())];
+                if carriage_return && !(found.is_some() && data.is_empty()) {
+                    hasher.write(b"\r");
+                    len += 1;
+                }
+                carriage_return = false;
+                if lines && data.last() == Some(&b'\r') {
+                    data = &data[..data.len() - 1];
+                    carriage_return = found.is_none();
+                }
+                hasher.write(data);
+                len += data.len() as u64;
+                match found {
+                    Some(index) => (index + 1, true),
+                    None => (available.len(), false),
+                }
+            };
+            self.reader.consume(used);
+            self.offset += used as u64;
+            if found {
+                break;
+            }
+        }
+        Some(Ok(Record {
+            offset,
+            len,
+            hash: hasher.finish(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::BuildHasherDefault;
+    use std::io::BufReader;
+    use x33a::X33a;
+
+    fn hash(bytes: &[u8]) -> u64 {
+        let mut hasher = X33a::new();
+        hasher.write(bytes);
+        hasher.finish()
+    }
+
+    ///
+    /// Gives at most `step` bytes per read, to split data across chunks.
+    ///
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn it_does_hash_streams_correctly() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        let expected = hash(&data);
+        assert_eq!(hash_reader::<X33a, _>(&data[..]).unwrap(), expected);
+        let mut reader = HashReader::new(
+            X33a::new(),
+            Trickle {
+                data: &data,
+                step: 7,
+            },
+        );
+        let mut writer = HashWriter::new(X33a::new(), Vec::new());
+        assert_eq!(io::copy(&mut reader, &mut writer).unwrap(), 200_000);
+        assert_eq!(reader.finish(), expected);
+        assert_eq!(writer.finish(), expected);
+        let (_, copied) = writer.into_parts();
+        assert_eq!(copied, data);
+        let mut buffer = [0u8; 3];
+        let mut short = HashWriter::new(X33a::new(), &mut buffer[..]);
+        assert_eq!(short.write(b"Ezra").unwrap(), 3);
+        assert_eq!(short.finish(), hash(b"Ezr"));
+    }
+
+    #[test]
+    fn it_does_hash_records_correctly() {
+        let input = b"alpha\r\nbeta\n\r\n\rgamma\r\rdelta\r";
+        let expected = [
+            (0, &b"alpha"[..]),
+            (7, b"beta"),
+            (12, b""),
+            (14, b"\rgamma\r\rdelta\r"),
+        ];
+        let build = BuildHasherDefault::<X33a>::default();
+        for step in 1..8 {
+            let reader = BufReader::with_capacity(step, Trickle { data: input, step });
+            let sut: Vec<Record> = lines(reader, build.clone()).map(|r| r.unwrap()).collect();
+            assert_eq!(sut.len(), expected.len());
+            for (record, &(offset, bytes)) in sut.iter().zip(expected.iter()) {
+                assert_eq!(record.offset, offset, "step {}", step);
+                assert_eq!(record.len, bytes.len() as u64);
+                assert_eq!(record.hash, hash(bytes), "step {}", step);
+            }
+        }
+        let sut: Vec<Record> = records(&b"a,,bc,"[..], build.clone(), b',')
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            sut,
+            vec![
+                Record {
+                    offset: 0,
+                    len: 1,
+                    hash: hash(b"a")
+                },
+                Record {
+                    offset: 2,
+                    len: 0,
+                    hash: hash(b"")
+                },
+                Record {
+                    offset: 3,
+                    len: 2,
+                    hash: hash(b"bc")
+                },
+            ]
+        );
+        assert_eq!(lines(&b""[..], build).count(), 0);
+    }
+}
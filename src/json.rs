@@ -0,0 +1,903 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Hashing the compact JSON form of any `serde::Serialize` value.
+//!
+//! PHP code often hashes `json_encode($value)`. `JsonSerializer` is a
+//! `serde::Serializer` that writes the same compact JSON that
+//! `serde_json::to_string()` gives straight into a hasher, with no `String`
+//! in between. It is only available with the `serde` feature.
+//!
+//! The output is byte for byte that of `serde_json` with its default
+//! settings: no whitespace, fields and map entries in the order they are
+//! serialized, floats in their shortest round trip form, `NaN` and infinite
+//! floats as `null`, and only `"`, `\` and control characters escaped. Use
+//! a `BTreeMap` where the order of the keys has to be canonical.
+//!
+//! Floats are formatted with zmij like `serde_json` 1.0.147 and later do.
+//! Earlier versions used ryu, which gives the same digits but writes an
+//! exponent like `1e20` where zmij writes `1e+20`.
+//!
+//! PHP escapes more by default. `JsonSerializer::php()` matches plain
+//! `json_encode()`, and the escape_*() options turn each escape on or off the
+//! way `JSON_UNESCAPED_SLASHES`, `JSON_UNESCAPED_UNICODE` and
+//! `JSON_UNESCAPED_LINE_TERMINATORS` do. Floats are the exception:
+//!
+//! * PHP writes a float with no fractional part like `1.0` as `1` unless
+//!   `JSON_PRESERVE_ZERO_FRACTION` is used, while `serde_json` and this
+//!   serializer always write `1.0`.
+//! * PHP always writes a fraction before an exponent, `1.0e+20` where zmij
+//!   writes `1e+20`, and only uses an exponent from 1e17 up and below 1e-4
+//!   where zmij does from 1e16 up and below 1e-5.
+//!
+//! # Examples
+//!
+//! ```rust
+//! # extern crate djb_hash;
+//! # extern crate serde;
+//! # extern crate serde_json;
+//! use std::collections::BTreeMap;
+//! use std::hash::Hasher;
+//! use djb_hash::json::JsonSerializer;
+//! use djb_hash::x33a_php::X33aPhp;
+//! # fn main() {
+//! let mut value = BTreeMap::new();
+//! value.insert("path", "/ä");
+//! let mut hasher1 = X33aPhp::new();
+//! djb_hash::json::write_json(&mut hasher1, &value).unwrap();
+//! let mut hasher2 = X33aPhp::new();
+//! hasher2.write(serde_json::to_string(&value).unwrap().as_bytes());
+//! assert_eq!(hasher1.finish(), hasher2.finish());
+//! // The same as PHP's json_encode(["path" => "/ä"]).
+//! let mut hasher3 = X33aPhp::new();
+//! serde::Serialize::serialize(&value, &mut JsonSerializer::php(&mut hasher3)).unwrap();
+//! let mut hasher4 = X33aPhp::new();
+//! hasher4.write(br#"{"path":"\/\u00e4"}"#);
+//! assert_eq!(hasher3.finish(), hasher4.finish());
+//! # }
+//! ```
+//!
+use serde::ser::{self, Impossible, Serialize};
+use std::error::Error;
+use std::fmt::{self, Display, Write};
+use std::hash::Hasher;
+use zmij;
+
+///
+/// Errors from serializing a value as JSON.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JsonError {
+    ///
+    /// A map key was not a string, a number, a bool or a char.
+    ///
+    KeyMustBeAString,
+    ///
+    /// A map key was a NaN or infinite float.
+    ///
+    FloatKeyMustBeFinite,
+    ///
+    /// The value's `Serialize` implementation failed.
+    ///
+    Custom(String),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonError::KeyMustBeAString => write!(f, "key must be a string"),
+            JsonError::FloatKeyMustBeFinite => {
+                write!(f, "float key must be finite (got NaN or +/-inf)")
+            }
+            JsonError::Custom(ref message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+impl ser::Error for JsonError {
+    fn custom<T: Display>(message: T) -> Self {
+        JsonError::Custom(message.to_string())
+    }
+}
+
+///
+/// Hashes JSON written by `JsonSerializer` with the default settings.
+///
+pub fn write_json<H, T>(hasher: &mut H, value: &T) -> Result<(), JsonError>
+where
+    H: Hasher,
+    T: Serialize + ?Sized,
+{
+    value.serialize(&mut JsonSerializer::new(hasher))
+}
+
+///
+/// Serializer that writes compact JSON into a hasher.
+///
+/// Any hasher in the crate can be used, either owned or as a `&mut`.
+///
+pub struct JsonSerializer<H> {
+    hasher: H,
+    escape_slashes: bool,
+    escape_unicode: bool,
+    escape_line_terminators: bool,
+}
+
+impl<H: Hasher> JsonSerializer<H> {
+    ///
+    /// Creates a new serializer that escapes the same as `serde_json`.
+    ///
+    pub fn new(hasher: H) -> Self {
+        JsonSerializer {
+            hasher,
+            escape_slashes: false,
+            escape_unicode: false,
+            escape_line_terminators: false,
+        }
+    }
+    ///
+    /// Creates a new serializer that escapes the same as PHP's
+    /// `json_encode()` with no flags.
+    ///
+    pub fn php(hasher: H) -> Self {
+        JsonSerializer::new(hasher)
+            .escape_slashes(true)
+            .escape_unicode(true)
+            .escape_line_terminators(true)
+    }
+    ///
+    /// Sets if `/` is written as `\/`, which PHP does unless given
+    /// `JSON_UNESCAPED_SLASHES`.
+    ///
+    pub fn escape_slashes(mut self, escape: bool) -> Self {
+        self.escape_slashes = escape;
+        self
+    }
+    ///
+    /// Sets if everything past ASCII is written as `\u` escapes of its UTF-16
+    /// code units, which PHP does unless given `JSON_UNESCAPED_UNICODE`.
+    ///
+    pub fn escape_unicode(mut self, escape: bool) -> Self {
+        self.escape_unicode = escape;
+        self
+    }
+    ///
+    /// Sets if U+2028 and U+2029 are written as `\u` escapes even when the
+    /// rest of Unicode is not, which PHP 7.1 and later do unless given
+    /// `JSON_UNESCAPED_LINE_TERMINATORS`.
+    ///
+    pub fn escape_line_terminators(mut self, escape: bool) -> Self {
+        self.escape_line_terminators = escape;
+        self
+    }
+    ///
+    /// Returns a reference to the hasher.
+    ///
+    pub fn get_ref(&self) -> &H {
+        &self.hasher
+    }
+    ///
+    /// Returns a mutable reference to the hasher.
+    ///
+    pub fn get_mut(&mut self) -> &mut H {
+        &mut self.hasher
+    }
+    ///
+    /// Returns the hasher.
+    ///
+    pub fn into_inner(self) -> H {
+        self.hasher
+    }
+    fn write_display<T: Display + ?Sized>(&mut self, value: &T) {
+        // Writing to a hasher never fails.
+        let _ = write!(Plain(&mut self.hasher), "{}", value);
+    }
+    fn write_f32(&mut self, value: f32) {
+        if value.is_finite() {
+            self.hasher
+                .write(zmij::Buffer::new().format_finite(value).as_bytes());
+        } else {
+            self.hasher.write(b"null");
+        }
+    }
+    fn write_f64(&mut self, value: f64) {
+        if value.is_finite() {
+            self.hasher
+                .write(zmij::Buffer::new().format_finite(value).as_bytes());
+        } else {
+            self.hasher.write(b"null");
+        }
+    }
+    fn write_str(&mut self, value: &str) {
+        self.hasher.write(b"\"");
+        self.write_escaped(value);
+        self.hasher.write(b"\"");
+    }
+    fn write_escaped(&mut self, value: &str) {
+        let bytes = value.as_bytes();
+        let mut start = 0;
+        for (index, c) in value.char_indices() {
+            let escape: &[u8] = match c {
+                '"' => b"\\\"",
+                '\\' => b"\\\\",
+                '\x08' => b"\\b",
+                '\x0c' => b"\\f",
+                '\n' => b"\\n",
+                '\r' => b"\\r",
+                '\t' => b"\\t",
+                '/' if self.escape_slashes => b"\\/",
+                '\x00'..='\x1f' => b"",
+                '\u{2028}' | '\u{2029}' if self.escape_line_terminators => b"",
+                '\u{80}'..='\u{10ffff}' if self.escape_unicode => b"",
+                _ => continue,
+            };
+            self.hasher.write(&bytes[start..index]);
+            if escape.is_empty() {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units).iter() {
+                    self.write_display(&format_args!("\\u{:04x}", unit));
+                }
+            } else {
+                self.hasher.write(escape);
+            }
+            start = index + c.len_utf8();
+        }
+        self.hasher.write(&bytes[start..]);
+    }
+}
+
+///
+/// Writes formatted text to the hasher as is.
+///
+struct Plain<'a, H: 'a>(&'a mut H);
+
+impl<'a, H: Hasher> Write for Plain<'a, H> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+///
+/// Writes formatted text to the hasher as the inside of a JSON string.
+///
+struct Escaped<'a, H: 'a>(&'a mut JsonSerializer<H>);
+
+impl<'a, H: Hasher> Write for Escaped<'a, H> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write_escaped(s);
+        Ok(())
+    }
+}
+
+impl<'a, H: Hasher> ser::Serializer for &'a mut JsonSerializer<H> {
+    type Ok = ();
+    type Error = JsonError;
+    type SerializeSeq = Compound<'a, H>;
+    type SerializeTuple = Compound<'a, H>;
+    type SerializeTupleStruct = Compound<'a, H>;
+    type SerializeTupleVariant = Compound<'a, H>;
+    type SerializeMap = Compound<'a, H>;
+    type SerializeStruct = Compound<'a, H>;
+    type SerializeStructVariant = Compound<'a, H>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), JsonError> {
+        self.hasher.write(if v { b"true" } else { b"false" });
+        Ok(())
+    }
+    fn serialize_i8(self, v: i8) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_i128(self, v: i128) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_u8(self, v: u8) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_u128(self, v: u128) -> Result<(), JsonError> {
+        self.write_display(&v);
+        Ok(())
+    }
+    fn serialize_f32(self, v: f32) -> Result<(), JsonError> {
+        self.write_f32(v);
+        Ok(())
+    }
+    fn serialize_f64(self, v: f64) -> Result<(), JsonError> {
+        self.write_f64(v);
+        Ok(())
+    }
+    fn serialize_char(self, v: char) -> Result<(), JsonError> {
+        self.write_str(v.encode_utf8(&mut [0u8; 4]));
+        Ok(())
+    }
+    fn serialize_str(self, v: &str) -> Result<(), JsonError> {
+        self.write_str(v);
+        Ok(())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), JsonError> {
+        use serde::ser::SerializeSeq;
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq.serialize_element(byte)?;
+        }
+        seq.end()
+    }
+    fn serialize_none(self) -> Result<(), JsonError> {
+        self.serialize_unit()
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<(), JsonError> {
+        self.hasher.write(b"null");
+        Ok(())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JsonError> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), JsonError> {
+        self.write_str(variant);
+        Ok(())
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.hasher.write(b"{");
+        self.write_str(variant);
+        self.hasher.write(b":");
+        value.serialize(&mut *self)?;
+        self.hasher.write(b"}");
+        Ok(())
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a, H>, JsonError> {
+        self.hasher.write(b"[");
+        Ok(Compound::new(self, b"]"))
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a, H>, JsonError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, H>, JsonError> {
+        self.serialize_seq(Some(len))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a, H>, JsonError> {
+        self.hasher.write(b"{");
+        self.write_str(variant);
+        self.hasher.write(b":[");
+        Ok(Compound::new(self, b"]}"))
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a, H>, JsonError> {
+        self.hasher.write(b"{");
+        Ok(Compound::new(self, b"}"))
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, H>, JsonError> {
+        self.serialize_map(Some(len))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a, H>, JsonError> {
+        self.hasher.write(b"{");
+        self.write_str(variant);
+        self.hasher.write(b":{");
+        Ok(Compound::new(self, b"}}"))
+    }
+    fn collect_str<T: Display + ?Sized>(self, value: &T) -> Result<(), JsonError> {
+        self.hasher.write(b"\"");
+        let _ = write!(Escaped(&mut *self), "{}", value);
+        self.hasher.write(b"\"");
+        Ok(())
+    }
+}
+
+///
+/// The serializer for JSON arrays and objects.
+///
+pub struct Compound<'a, H: 'a> {
+    serializer: &'a mut JsonSerializer<H>,
+    first: bool,
+    end: &'static [u8],
+}
+
+impl<'a, H: Hasher> Compound<'a, H> {
+    fn new(serializer: &'a mut JsonSerializer<H>, end: &'static [u8]) -> Self {
+        Compound {
+            serializer,
+            first: true,
+            end,
+        }
+    }
+    fn comma(&mut self) {
+        if !self.first {
+            self.serializer.hasher.write(b",");
+        }
+        self.first = false;
+    }
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.comma();
+        value.serialize(&mut *self.serializer)
+    }
+    fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), JsonError> {
+        self.comma();
+        self.serializer.write_str(key);
+        self.serializer.hasher.write(b":");
+        value.serialize(&mut *self.serializer)
+    }
+    fn finish(self) -> Result<(), JsonError> {
+        self.serializer.hasher.write(self.end);
+        Ok(())
+    }
+}
+
+impl<'a, H: Hasher> ser::SerializeSeq for Compound<'a, H> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.finish()
+    }
+}
+
+impl<'a, H: Hasher> ser::SerializeTuple for Compound<'a, H> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.finish()
+    }
+}
+
+impl<'a, H: Hasher> ser::SerializeTupleStruct for Compound<'a, H> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.finish()
+    }
+}
+
+impl<'a, H: Hasher> ser::SerializeTupleVariant for Compound<'a, H> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.element(value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.finish()
+    }
+}
+
+impl<'a, H: Hasher> ser::SerializeMap for Compound<'a, H> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), JsonError> {
+        self.comma();
+        key.serialize(MapKey(&mut *self.serializer))
+    }
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+        self.serializer.hasher.write(b":");
+        value.serialize(&mut *self.serializer)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.finish()
+    }
+}
+
+impl<'a, H: Hasher> ser::SerializeStruct for Compound<'a, H> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.finish()
+    }
+}
+
+impl<'a, H: Hasher> ser::SerializeStructVariant for Compound<'a, H> {
+    type Ok = ();
+    type Error = JsonError;
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        self.field(key, value)
+    }
+    fn end(self) -> Result<(), JsonError> {
+        self.finish()
+    }
+}
+
+///
+/// Serializer for map keys, which JSON only allows to be strings.
+///
+/// Numbers, bools and chars are quoted the same as `serde_json` does.
+///
+struct MapKey<'a, H: 'a>(&'a mut JsonSerializer<H>);
+
+impl<'a, H: Hasher> MapKey<'a, H> {
+    fn quoted<F: FnOnce(&mut JsonSerializer<H>)>(self, write: F) -> Result<(), JsonError> {
+        self.0.hasher.write(b"\"");
+        write(&mut *self.0);
+        self.0.hasher.write(b"\"");
+        Ok(())
+    }
+}
+
+impl<'a, H: Hasher> ser::Serializer for MapKey<'a, H> {
+    type Ok = ();
+    type Error = JsonError;
+    type SerializeSeq = Impossible<(), JsonError>;
+    type SerializeTuple = Impossible<(), JsonError>;
+    type SerializeTupleStruct = Impossible<(), JsonError>;
+    type SerializeTupleVariant = Impossible<(), JsonError>;
+    type SerializeMap = Impossible<(), JsonError>;
+    type SerializeStruct = Impossible<(), JsonError>;
+    type SerializeStructVariant = Impossible<(), JsonError>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), JsonError> {
+        self.quoted(|s| s.hasher.write(if v { b"true" } else { b"false" }))
+    }
+    fn serialize_i8(self, v: i8) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_i16(self, v: i16) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_i32(self, v: i32) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_i64(self, v: i64) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_i128(self, v: i128) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_u8(self, v: u8) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_u16(self, v: u16) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_u32(self, v: u32) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_u64(self, v: u64) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_u128(self, v: u128) -> Result<(), JsonError> {
+        self.quoted(|s| s.write_display(&v))
+    }
+    fn serialize_f32(self, v: f32) -> Result<(), JsonError> {
+        if !v.is_finite() {
+            return Err(JsonError::FloatKeyMustBeFinite);
+        }
+        self.quoted(|s| s.write_f32(v))
+    }
+    fn serialize_f64(self, v: f64) -> Result<(), JsonError> {
+        if !v.is_finite() {
+            return Err(JsonError::FloatKeyMustBeFinite);
+        }
+        self.quoted(|s| s.write_f64(v))
+    }
+    fn serialize_char(self, v: char) -> Result<(), JsonError> {
+        self.0.write_str(v.encode_utf8(&mut [0u8; 4]));
+        Ok(())
+    }
+    fn serialize_str(self, v: &str) -> Result<(), JsonError> {
+        self.0.write_str(v);
+        Ok(())
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_none(self) -> Result<(), JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<(), JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<(), JsonError> {
+        self.0.write_str(variant);
+        Ok(())
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), JsonError> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, JsonError> {
+        Err(JsonError::KeyMustBeAString)
+    }
+    fn collect_str<T: Display + ?Sized>(self, value: &T) -> Result<(), JsonError> {
+        self.0.collect_str(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+    use std::collections::BTreeMap;
+    use x33a_php::X33aPhp;
+
+    struct Bytes(Vec<u8>);
+
+    impl Hasher for Bytes {
+        fn finish(&self) -> u64 {
+            0
+        }
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
+
+    fn json<T: Serialize + ?Sized>(value: &T) -> Result<String, JsonError> {
+        let mut bytes = Bytes(Vec::new());
+        write_json(&mut bytes, value)?;
+        Ok(String::from_utf8(bytes.0).unwrap())
+    }
+
+    fn php<T: Serialize + ?Sized>(value: &T) -> String {
+        let mut serializer = JsonSerializer::php(Bytes(Vec::new()));
+        value.serialize(&mut serializer).unwrap();
+        String::from_utf8(serializer.into_inner().0).unwrap()
+    }
+
+    #[derive(Serialize)]
+    struct Unit;
+
+    #[derive(Serialize)]
+    struct Newtype(u8);
+
+    #[derive(Serialize)]
+    struct Point(i32, i32);
+
+    #[derive(Serialize)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Line(Point, Point),
+        Rect { width: u32, height: u32 },
+    }
+
+    #[derive(Serialize)]
+    struct Record<'a> {
+        name: &'a str,
+        tags: Vec<&'a str>,
+        score: Option<f32>,
+        missing: Option<u8>,
+        shapes: Vec<Shape>,
+        #[serde(with = "serde_bytes_as_seq")]
+        raw: &'a [u8],
+        unit: Unit,
+        newtype: Newtype,
+        tuple: (u8, char, bool, ()),
+        big: (i128, u128),
+        floats: [f64; 6],
+        nested: BTreeMap<i32, BTreeMap<char, bool>>,
+    }
+
+    mod serde_bytes_as_seq {
+        pub fn serialize<S: ::serde::Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+            s.serialize_bytes(v)
+        }
+    }
+
+    #[test]
+    fn it_does_serialize_correctly() {
+        let mut nested = BTreeMap::new();
+        let mut inner = BTreeMap::new();
+        inner.insert('k', true);
+        inner.insert('é', false);
+        nested.insert(-1, inner);
+        nested.insert(2, BTreeMap::new());
+        let record = Record {
+            name: "a \"quoted\" \\ path/ é 😀 \u{2028} \x00\x08\x0c\n\r\t\x1f\x7f",
+            tags: vec![],
+            score: Some(0.1),
+            missing: None,
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle(2.5),
+                Shape::Line(Point(0, -1), Point(3, 4)),
+                Shape::Rect {
+                    width: 3,
+                    height: 4,
+                },
+            ],
+            raw: b"\x00\xff",
+            unit: Unit,
+            newtype: Newtype(7),
+            tuple: (255, '"', true, ()),
+            big: (i128::MIN, u128::MAX),
+            floats: [1.0, -0.0, 1e20, 1.5e-7, f64::NAN, f64::INFINITY],
+            nested,
+        };
+        let expected = serde_json::to_string(&record).unwrap();
+        assert_eq!(json(&record), Ok(expected.clone()));
+        let mut keys: BTreeMap<String, u8> = BTreeMap::new();
+        keys.insert("a/b".to_string(), 1);
+        let mut floats = BTreeMap::new();
+        floats.insert(true, 1.5f32);
+        floats.insert(false, f32::NAN);
+        assert_eq!(
+            json(&floats).unwrap(),
+            serde_json::to_string(&floats).unwrap()
+        );
+        let mut hasher1 = X33aPhp::new();
+        write_json(&mut hasher1, &record).unwrap();
+        let mut hasher2 = X33aPhp::new();
+        hasher2.write(expected.as_bytes());
+        assert_eq!(hasher1.finish(), hasher2.finish());
+        assert_eq!(php(&keys), r#"{"a\/b":1}"#);
+        assert_eq!(php("é😀\u{2028}/"), r#""\u00e9\ud83d\ude00\u2028\/""#);
+        let mut line = JsonSerializer::new(Bytes(Vec::new())).escape_line_terminators(true);
+        "é\u{2029}".serialize(&mut line).unwrap();
+        assert_eq!(line.into_inner().0, "\"é\\u2029\"".as_bytes());
+        let mut bad = BTreeMap::new();
+        bad.insert(vec![1u8], 1);
+        assert_eq!(json(&bad), Err(JsonError::KeyMustBeAString));
+        let mut display = Bytes(Vec::new());
+        ser::Serializer::collect_str(&mut JsonSerializer::new(&mut display), &"a\"b").unwrap();
+        assert_eq!(display.0, b"\"a\\\"b\"");
+    }
+}
@@ -76,12 +76,123 @@
 //! this is in PHP where the high bit is always set because they use a zero hash
 //! value to detect an unset hash internally.
 //!
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_derive;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(feature = "serde")]
+extern crate zmij;
+
 use std::hash::Hasher;
 
+pub use algorithm::Algorithm;
+pub use hash_value::HashValue;
+pub use int_encoding::IntEncoding;
+pub use structural::DjbHash;
+pub use vectored::HasherVectored;
+
+///
+/// Overrides all of the integer write_*() functions of `Hasher` to go through
+/// `int_encoding::write_int()` with the `IntEncoding` in the named field.
+///
+macro_rules! write_int_functions {
+    ($field:ident) => {
+        fn write_u8(&mut self, i: u8) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as u128, 1);
+        }
+        fn write_u16(&mut self, i: u16) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as u128, 2);
+        }
+        fn write_u32(&mut self, i: u32) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as u128, 4);
+        }
+        fn write_u64(&mut self, i: u64) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as u128, 8);
+        }
+        fn write_u128(&mut self, i: u128) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i, 16);
+        }
+        fn write_usize(&mut self, i: usize) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as u64 as u128, 8);
+        }
+        fn write_i8(&mut self, i: i8) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as i128 as u128, 1);
+        }
+        fn write_i16(&mut self, i: i16) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as i128 as u128, 2);
+        }
+        fn write_i32(&mut self, i: i32) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as i128 as u128, 4);
+        }
+        fn write_i64(&mut self, i: i64) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as i128 as u128, 8);
+        }
+        fn write_i128(&mut self, i: i128) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as u128, 16);
+        }
+        fn write_isize(&mut self, i: isize) {
+            let encoding = self.$field;
+            ::int_encoding::write_int(self, encoding, i as i64 as i128 as u128, 8);
+        }
+    };
+}
+
+pub mod algorithm;
+pub mod analysis;
+pub mod apr;
+pub mod berkeley_db;
+pub mod cdb;
+pub mod checkpoint;
+pub mod elf;
+pub mod elf_gnu_hash;
+pub mod elf_hash;
+pub mod elf_sysv_hash;
+pub mod encoding;
+pub mod fold;
+pub mod g_str_hash;
+pub mod hash_value;
+pub mod int_encoding;
+pub mod interop;
+pub mod io;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod libiberty_hash;
+pub mod lua_hash;
+pub mod nginx;
+pub mod perl_hash;
+pub mod phf;
+pub mod py2_dict;
+pub mod py2_str_hash;
+pub mod py2_str_hash_u32;
+pub mod redis;
+pub mod sdbm;
+pub mod structural;
+pub mod tcl_hash;
+pub mod trace;
+pub mod tune;
+pub mod vectored;
 pub mod x33a;
 pub mod x33a_php;
+pub mod x33a_php_signed;
+pub mod x33a_signed;
 pub mod x33a_u32;
 pub mod x33a_u32_php;
+pub mod x33a_u32_php_signed;
+pub mod x33a_u32_signed;
 pub mod x33x;
 pub mod x33x_u32;
 
@@ -99,5 +210,20 @@ pub trait HasherU32: Hasher {
     ///
     fn finish_u32(&self) -> u32;
 }
+
+///
+/// This trait is used by hashes that can take a whole code unit in one step.
+///
+/// Where write() adds a string one byte at a time, write_unit() does the same
+/// multiply and add, or XOR, with a UTF-16 or UTF-32 code unit as the value.
+/// That matches implementations in languages like JS and Java that loop over
+/// the code units of their strings instead of over bytes.
+///
+pub trait HasherUnit: Hasher {
+    ///
+    /// Writes a single code unit to the hash.
+    ///
+    fn write_unit(&mut self, unit: u32);
+}
 #[cfg(test)]
 mod tests {}
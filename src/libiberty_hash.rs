@@ -0,0 +1,181 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{flag, Checkpoint, CheckpointError, Resumable};
+use super::HasherU32;
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements `htab_hash_string()` from GCC's libiberty `hashtab.c`, which is also used by
+/// binutils and GDB.
+///
+/// It is a 32 bit times 67 hash, r = r * 67 + byte - 113, over unsigned bytes starting from 0
+/// that stops at the first NUL byte. Once a NUL byte is written any later bytes are ignored.
+/// libiberty hash tables use prime sizes and pick the slot with hash % size.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::libiberty_hash::*;
+/// let input = "hello";
+/// let mut hasher = LibibertyHash::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish(), 4109975269u64);
+/// assert_eq!(hasher.finish_u32(), 4109975269u32);
+/// ```
+///
+pub struct LibibertyHash {
+    hash: u32,
+    salt: u32,
+    consumed: u64,
+    ended: bool,
+    int_encoding: IntEncoding,
+}
+
+impl LibibertyHash {
+    ///
+    /// Creates a new hash starting from zero like libiberty does.
+    ///
+    pub fn new() -> Self {
+        LibibertyHash {
+            hash: 0,
+            salt: 0,
+            consumed: 0,
+            ended: false,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for LibibertyHash {
+    ///
+    /// Same as new(), starting from zero.
+    ///
+    fn default() -> Self {
+        LibibertyHash::new()
+    }
+}
+
+impl HasherU32 for LibibertyHash {
+    fn finish_u32(&self) -> u32 {
+        self.hash
+    }
+}
+
+impl WriteIdentity for LibibertyHash {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        if !self.ended {
+            self.hash = self.hash.wrapping_mul(67).wrapping_add(value as u32).wrapping_sub(113);
+        }
+    }
+}
+
+impl Hasher for LibibertyHash {
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does hash * 67 + byte - 113 until the first NUL byte.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        if self.ended {
+            return;
+        }
+        for byte in bytes {
+            if *byte == 0 {
+                self.ended = true;
+                return;
+            }
+            self.hash = self.hash.wrapping_mul(67).wrapping_add(*byte as u32).wrapping_sub(113);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for LibibertyHash {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            extra: vec![self.ended as u8],
+            ..Checkpoint::new(Algorithm::LibibertyHash, self.salt as u64, self.hash as u64, self.consumed, self.int_encoding)
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        let extra = checkpoint.expect(Algorithm::LibibertyHash, 1)?;
+        Ok(LibibertyHash {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            consumed: checkpoint.consumed,
+            ended: flag(extra[0])?,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let vectors: [(&[u8], u32); 6] = [
+            (b"", 0),
+            (b"a", 4294967280),
+            (b"Ez", 4294964357),
+            (b"FY", 4294964391),
+            (b"hello world", 333146327),
+            (b"caf\xc3\xa9", 4007995565),
+        ];
+        for &(input, expected) in vectors.iter() {
+            let mut sut = LibibertyHash::new();
+            sut.write(input);
+            assert_eq!(sut.finish_u32(), expected);
+        }
+        let mut sut = LibibertyHash::new();
+        sut.write(b"abc\0def");
+        assert_eq!(sut.finish_u32(), 4294894453);
+    }
+}
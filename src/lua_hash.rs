@@ -0,0 +1,323 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Emulation of the string hash used by Lua 5.1 to 5.3.
+//!
+//! Lua seeds its hash with the length of the string, then walks the string
+//! backwards doing `h ^ ((h << 5) + (h >> 2) + c)`, a shift-add-xor cousin of
+//! X33x. To keep long strings cheap it only samples every `step`th byte, where
+//! `step = (len >> LUAI_HASHLIMIT) + 1` and `LUAI_HASHLIMIT` is 5 unless
+//! changed in `luaconf.h`, so strings of 32 bytes or more are not fully
+//! hashed.
+//!
+//! Lua 5.2 and 5.3 also XOR a per state seed into the length. By default the
+//! seed is made from addresses at start up by `luai_makeseed()`, so it has to
+//! be read out of, or fixed in, the embedded interpreter to predict where
+//! strings land. Lua 5.1 has no seed. The vectors in the tests were worked
+//! out from `lstring.c` of each version.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use djb_hash::HasherU32;
+//! use djb_hash::lua_hash::*;
+//! let mut hasher = LuaHash::new(LuaVersion::Lua51);
+//! hasher.write(b"hello");
+//! assert_eq!(hasher.finish_u32(), 287972584);
+//! let mut hasher = LuaHash::new_with_salt(LuaVersion::Lua53, 0x1234_5678);
+//! hasher.write(b"hello");
+//! assert_eq!(hasher.finish_u32(), 3114224051);
+//! ```
+//!
+use checkpoint::{Checkpoint, CheckpointError, Resumable};
+use int_encoding::WriteIdentity;
+use std::hash::Hasher;
+use Algorithm;
+use HasherU32;
+use IntEncoding;
+
+///
+/// The default `LUAI_HASHLIMIT` from `luaconf.h`.
+///
+pub const LUAI_HASHLIMIT: u32 = 5;
+
+///
+/// The Lua versions that share this string hash.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LuaVersion {
+    Lua51,
+    Lua52,
+    Lua53,
+}
+
+///
+/// Implements Lua's `luaS_hash()` as a hasher.
+///
+/// Lua hashes the string from its end and mixes in the length first, so
+/// everything written is kept until finish() is called.
+///
+#[derive(Clone, Debug)]
+pub struct LuaHash {
+    version: LuaVersion,
+    seed: u32,
+    hash_limit: u32,
+    bytes: Vec<u8>,
+    int_encoding: IntEncoding,
+}
+
+impl LuaHash {
+    ///
+    /// Creates a new hash for the version using a seed of 0.
+    ///
+    pub fn new(version: LuaVersion) -> Self {
+        LuaHash::new_with_salt(version, 0)
+    }
+    ///
+    /// Creates a new hash for the version using the seed of a Lua state.
+    ///
+    /// Lua 5.1 has no seed so it is ignored for that version.
+    ///
+    pub fn new_with_salt(version: LuaVersion, seed: u32) -> Self {
+        LuaHash {
+            version,
+            seed,
+            hash_limit: LUAI_HASHLIMIT,
+            bytes: Vec::new(),
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Sets `LUAI_HASHLIMIT` for interpreters built with a changed `luaconf.h`.
+    ///
+    pub fn hash_limit(mut self, hash_limit: u32) -> Self {
+        self.hash_limit = hash_limit;
+        self
+    }
+    ///
+    /// Returns the indexes of the bytes written so far that Lua would hash.
+    ///
+    pub fn sampled_indices(&self) -> SampledIndices {
+        sampled_indices(self.bytes.len(), self.hash_limit)
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl HasherU32 for LuaHash {
+    fn finish_u32(&self) -> u32 {
+        let len = self.bytes.len() as u32;
+        let mut hash = match self.version {
+            LuaVersion::Lua51 => len,
+            LuaVersion::Lua52 | LuaVersion::Lua53 => self.seed ^ len,
+        };
+        for i in self.sampled_indices() {
+            hash ^= (hash << 5)
+                .wrapping_add(hash >> 2)
+                .wrapping_add(self.bytes[i] as u32);
+        }
+        hash
+    }
+}
+
+impl WriteIdentity for LuaHash {
+    const BITS: u32 = 64;
+    ///
+    /// Lua only hashes strings so the integer goes in as the decimal text
+    /// `tostring()` gives a Lua 5.3 integer.
+    ///
+    fn write_identity(&mut self, value: u64) {
+        self.bytes
+            .extend_from_slice((value as i64).to_string().as_bytes());
+    }
+}
+
+impl Hasher for LuaHash {
+    fn finish(&self) -> u64 {
+        self.finish_u32() as u64
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for LuaHash {
+    ///
+    /// The salt is the seed and, as nothing is hashed until finish(), the
+    /// bytes written so far are kept in the extra bytes.
+    ///
+    fn checkpoint(&self) -> Checkpoint {
+        let version = match self.version {
+            LuaVersion::Lua51 => 51,
+            LuaVersion::Lua52 => 52,
+            LuaVersion::Lua53 => 53,
+        };
+        let mut extra = vec![version];
+        extra.extend_from_slice(&self.hash_limit.to_le_bytes());
+        extra.extend_from_slice(&self.bytes);
+        Checkpoint {
+            extra,
+            ..Checkpoint::new(
+                Algorithm::LuaHash,
+                self.seed as u64,
+                0,
+                self.bytes.len() as u64,
+                self.int_encoding,
+            )
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        let extra = checkpoint.check(Algorithm::LuaHash)?;
+        if extra.len() < 5 || (extra.len() - 5) as u64 != checkpoint.consumed {
+            return Err(CheckpointError::InvalidExtra);
+        }
+        if checkpoint.state != 0 {
+            return Err(CheckpointError::OutOfRange);
+        }
+        let version = match extra[0] {
+            51 => LuaVersion::Lua51,
+            52 => LuaVersion::Lua52,
+            53 => LuaVersion::Lua53,
+            _ => return Err(CheckpointError::InvalidExtra),
+        };
+        Ok(LuaHash {
+            version,
+            seed: checkpoint.salt as u32,
+            hash_limit: u32::from_le_bytes([extra[1], extra[2], extra[3], extra[4]]),
+            bytes: extra[5..].to_vec(),
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+
+///
+/// Returns the indexes of the bytes Lua hashes for a string of `len` bytes, in
+/// the order they are hashed.
+///
+/// # Examples
+///
+/// ```rust
+/// use djb_hash::lua_hash::*;
+/// let sampled: Vec<usize> = sampled_indices(4, LUAI_HASHLIMIT).collect();
+/// assert_eq!(sampled, vec![3, 2, 1, 0]);
+/// let sampled: Vec<usize> = sampled_indices(95, LUAI_HASHLIMIT).collect();
+/// assert_eq!(sampled.len(), 31);
+/// assert_eq!(&sampled[..3], &[94, 91, 88]);
+/// ```
+///
+pub fn sampled_indices(len: usize, hash_limit: u32) -> SampledIndices {
+    let step = len.checked_shr(hash_limit).unwrap_or(0) + 1;
+    SampledIndices {
+        remaining: len,
+        step,
+    }
+}
+
+///
+/// Iterator over the indexes of the bytes Lua hashes.
+///
+#[derive(Clone, Debug)]
+pub struct SampledIndices {
+    remaining: usize,
+    step: usize,
+}
+
+impl Iterator for SampledIndices {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.remaining < self.step {
+            return None;
+        }
+        let index = self.remaining - 1;
+        self.remaining -= self.step;
+        Some(index)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.remaining / self.step;
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for SampledIndices {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let long: Vec<u8> = (32..127).collect();
+        let vectors: [(&[u8], u32, u32, u32); 6] = [
+            (b"", 0, 305_419_896, 0),
+            (b"a", 128, 1_495_511_910, 128),
+            (b"Ez", 6091, 1_671_225_967, 184),
+            (b"FY", 4951, 1_671_226_868, 155),
+            (
+                b"abcdefghijklmnopqrstuvwxyz0123456789",
+                2_731_011_768,
+                1_959_295_455,
+                1254,
+            ),
+            (&long, 2_256_521_196, 388_587_723, 3114),
+        ];
+        for &(input, unseeded, seeded, limit_one) in &vectors {
+            for &version in &[LuaVersion::Lua51, LuaVersion::Lua52, LuaVersion::Lua53] {
+                let mut sut = LuaHash::new(version);
+                sut.write(input);
+                assert_eq!(sut.finish_u32(), unseeded);
+                let mut sut = LuaHash::new(version).hash_limit(1);
+                sut.write(input);
+                assert_eq!(sut.finish(), limit_one as u64);
+            }
+            let mut sut = LuaHash::new_with_salt(LuaVersion::Lua52, 0x1234_5678);
+            sut.write(input);
+            assert_eq!(sut.finish_u32(), seeded);
+            let mut sut = LuaHash::new_with_salt(LuaVersion::Lua51, 0x1234_5678);
+            sut.write(input);
+            assert_eq!(sut.finish_u32(), unseeded);
+        }
+        let mut sut = LuaHash::new(LuaVersion::Lua53);
+        sut.write(&long);
+        let sampled = sut.sampled_indices();
+        assert_eq!(sampled.len(), 31);
+        assert_eq!(sampled.last(), Some(4));
+        assert_eq!(sampled_indices(0, LUAI_HASHLIMIT).count(), 0);
+        assert_eq!(sampled_indices(10, 64).count(), 10);
+    }
+}
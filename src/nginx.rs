@@ -0,0 +1,812 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Emulation of the nginx `ngx_hash_key()` hashes and of the bucket sizing done
+//! by `ngx_hash_init()`.
+//!
+//! nginx hashes header and server names with `key * 31 + c` starting from 0
+//! in an `ngx_uint_t`, which is 64 bits on 64 bit builds and 32 bits on 32 bit
+//! ones. finish() gives the 64 bit value and finish_u32() the 32 bit one.
+//!
+//! The simulator repeats the search `ngx_hash_init()` does for the smallest
+//! table size where no bucket is larger than the bucket size, so settings like
+//! `server_names_hash_max_size` and `server_names_hash_bucket_size` can be
+//! worked out offline instead of by trial and error with `nginx -t`. Server
+//! names with wildcards are split into the exact, head wildcard and tail
+//! wildcard tables the same way `ngx_hash_add_key()` and
+//! `ngx_hash_wildcard_init()` do.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::nginx::*;
+//! let names = ["example.com", "www.example.com", "*.example.org", "mail.example.*"];
+//! // server_names_hash_max_size 512 and server_names_hash_bucket_size 64.
+//! let report = NgxHashInit::new(512, 64).server_names(&names).unwrap();
+//! assert!(report.optimal());
+//! assert_eq!(report.exact.unwrap().size, 1);
+//! assert!(report.max_size() <= 512);
+//! ```
+//!
+use checkpoint::{Checkpoint, CheckpointError, Resumable};
+use int_encoding::WriteIdentity;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hasher;
+use Algorithm;
+use HasherU32;
+use IntEncoding;
+
+///
+/// Implements nginx's `ngx_hash_key()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::nginx::*;
+/// let mut hasher = NgxHashKey::new();
+/// hasher.write(b"Content-Type");
+/// assert_eq!(hasher.finish(), 1796356865476601934u64);
+/// assert_eq!(hasher.finish_u32(), 949037134u32);
+/// ```
+///
+pub struct NgxHashKey {
+    hash: u64,
+    salt: u64,
+    consumed: u64,
+    int_encoding: IntEncoding,
+}
+
+impl NgxHashKey {
+    ///
+    /// Creates a new hash starting from zero like nginx does.
+    ///
+    pub fn new() -> Self {
+        NgxHashKey {
+            hash: 0,
+            salt: 0,
+            consumed: 0,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for NgxHashKey {
+    ///
+    /// Same as new(), starting from zero.
+    ///
+    fn default() -> Self {
+        NgxHashKey::new()
+    }
+}
+
+impl HasherU32 for NgxHashKey {
+    ///
+    /// Returns the hash a 32 bit build of nginx gives.
+    ///
+    fn finish_u32(&self) -> u32 {
+        self.hash as u32
+    }
+}
+
+impl WriteIdentity for NgxHashKey {
+    const BITS: u32 = 64;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 5).wrapping_sub(self.hash).wrapping_add(value);
+    }
+}
+
+impl Hasher for NgxHashKey {
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does hash * 31 + byte but is implemented as hash << 5 (*32) - hash + byte.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        for byte in bytes {
+            self.hash = (self.hash << 5)
+                .wrapping_sub(self.hash)
+                .wrapping_add(*byte as u64);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for NgxHashKey {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(
+            Algorithm::NgxHashKey,
+            self.salt,
+            self.hash,
+            self.consumed,
+            self.int_encoding,
+        )
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        checkpoint.expect(Algorithm::NgxHashKey, 0)?;
+        Ok(NgxHashKey {
+            hash: checkpoint.state,
+            salt: checkpoint.salt,
+            consumed: checkpoint.consumed,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+
+///
+/// Implements nginx's `ngx_hash_key_lc()`, which lower cases ASCII letters
+/// before hashing them.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::nginx::*;
+/// let mut hasher1 = NgxHashKeyLc::new();
+/// let mut hasher2 = NgxHashKey::new();
+/// hasher1.write(b"Host");
+/// hasher2.write(b"host");
+/// assert_eq!(hasher1.finish(), 3208616u64);
+/// assert_eq!(hasher1.finish(), hasher2.finish());
+/// ```
+///
+pub struct NgxHashKeyLc {
+    hasher: NgxHashKey,
+    int_encoding: IntEncoding,
+}
+
+impl NgxHashKeyLc {
+    ///
+    /// Creates a new hash starting from zero like nginx does.
+    ///
+    pub fn new() -> Self {
+        NgxHashKeyLc {
+            hasher: NgxHashKey::new(),
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for NgxHashKeyLc {
+    ///
+    /// Same as new(), starting from zero.
+    ///
+    fn default() -> Self {
+        NgxHashKeyLc::new()
+    }
+}
+
+impl HasherU32 for NgxHashKeyLc {
+    ///
+    /// Returns the hash a 32 bit build of nginx gives.
+    ///
+    fn finish_u32(&self) -> u32 {
+        self.hasher.finish_u32()
+    }
+}
+
+impl WriteIdentity for NgxHashKeyLc {
+    const BITS: u32 = 64;
+    fn write_identity(&mut self, value: u64) {
+        self.hasher.write_identity(value);
+    }
+}
+
+impl Hasher for NgxHashKeyLc {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        let mut buffer = [0u8; 64];
+        for chunk in bytes.chunks(buffer.len()) {
+            for (lower, byte) in buffer.iter_mut().zip(chunk) {
+                *lower = byte.to_ascii_lowercase();
+            }
+            self.hasher.write(&buffer[..chunk.len()]);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for NgxHashKeyLc {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            algorithm: Algorithm::NgxHashKeyLc,
+            int_encoding: self.int_encoding,
+            ..self.hasher.checkpoint()
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        checkpoint.expect(Algorithm::NgxHashKeyLc, 0)?;
+        let inner = Checkpoint {
+            algorithm: Algorithm::NgxHashKey,
+            int_encoding: IntEncoding::LittleEndian,
+            ..checkpoint.clone()
+        };
+        Ok(NgxHashKeyLc {
+            hasher: NgxHashKey::restore(&inner)?,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+
+///
+/// Returns the `ngx_hash_key_lc()` hash of the name.
+///
+pub fn ngx_hash_key_lc(name: &[u8]) -> u64 {
+    let mut hasher = NgxHashKeyLc::new();
+    hasher.write(name);
+    hasher.finish()
+}
+
+///
+/// Errors `ngx_hash_init()` and `ngx_hash_add_key()` would stop nginx with.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NgxHashError {
+    ///
+    /// The max size is zero.
+    ///
+    MaxSizeZero,
+    ///
+    /// The bucket size is over 65536 minus the cache line size.
+    ///
+    BucketSizeTooBig,
+    ///
+    /// A name does not fit in a bucket of the bucket size. Holds the name and
+    /// the smallest bucket size it fits in. The name is empty when the bucket
+    /// size is too small for any name, under 3 pointers.
+    ///
+    BucketSizeTooSmall(Vec<u8>, usize),
+    ///
+    /// A bucket at the max size grew past what nginx can address.
+    ///
+    MaxSizeTooSmall,
+    ///
+    /// A server name has a `*` or `..` somewhere nginx does not allow it.
+    ///
+    InvalidName(Vec<u8>),
+}
+
+impl fmt::Display for NgxHashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            NgxHashError::MaxSizeZero => {
+                write!(f, "could not build hash, you should increase max_size")
+            }
+            NgxHashError::BucketSizeTooBig => write!(f, "bucket_size is too big"),
+            NgxHashError::BucketSizeTooSmall(ref name, needed) => write!(
+                f,
+                "could not build hash for \"{}\", you should increase bucket_size: {}",
+                String::from_utf8_lossy(name),
+                needed
+            ),
+            NgxHashError::MaxSizeTooSmall => {
+                write!(f, "could not build hash, you should increase max_size")
+            }
+            NgxHashError::InvalidName(ref name) => write!(
+                f,
+                "invalid server name or wildcard \"{}\"",
+                String::from_utf8_lossy(name)
+            ),
+        }
+    }
+}
+
+impl Error for NgxHashError {}
+
+///
+/// The table one `ngx_hash_init()` call would build.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NgxHashReport {
+    ///
+    /// The number of buckets, the smallest that fits when optimal.
+    ///
+    pub size: usize,
+    ///
+    /// False when no size up to the max size fits and nginx falls back to the
+    /// max size with a "could not build optimal" warning.
+    ///
+    pub optimal: bool,
+    ///
+    /// The smallest bucket size every name fits in on its own.
+    ///
+    pub min_bucket_size: usize,
+    ///
+    /// The bytes used by the buckets after aligning each to a cache line.
+    ///
+    pub bytes: usize,
+}
+
+///
+/// Every table built for a list of server names.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NgxServerNamesReport {
+    ///
+    /// The exact names table, if there were any exact names.
+    ///
+    pub exact: Option<NgxHashReport>,
+    ///
+    /// Every level of the `*.example.com` and `.example.com` tables.
+    ///
+    pub head: Vec<NgxHashReport>,
+    ///
+    /// Every level of the `www.example.*` tables.
+    ///
+    pub tail: Vec<NgxHashReport>,
+}
+
+impl NgxServerNamesReport {
+    ///
+    /// Iterates over every table.
+    ///
+    pub fn tables(&self) -> impl Iterator<Item = &NgxHashReport> {
+        self.exact.iter().chain(&self.head).chain(&self.tail)
+    }
+    ///
+    /// Returns the smallest max size every table fits in.
+    ///
+    pub fn max_size(&self) -> usize {
+        self.tables().map(|t| t.size).max().unwrap_or(0)
+    }
+    ///
+    /// Returns the smallest bucket size every name fits in.
+    ///
+    pub fn min_bucket_size(&self) -> usize {
+        self.tables().map(|t| t.min_bucket_size).max().unwrap_or(0)
+    }
+    ///
+    /// Returns true if every table was built without the "could not build
+    /// optimal" warning.
+    ///
+    pub fn optimal(&self) -> bool {
+        self.tables().all(|t| t.optimal)
+    }
+}
+
+///
+/// Simulates `ngx_hash_init()` for a max size and bucket size.
+///
+/// The sizes of pointers and cache lines change the result, so they default
+/// to the usual x86_64 values of 8 and 64 and can be set for other targets.
+///
+#[derive(Clone, Debug)]
+pub struct NgxHashInit {
+    max_size: usize,
+    bucket_size: usize,
+    pointer_size: usize,
+    cacheline_size: usize,
+}
+
+impl NgxHashInit {
+    ///
+    /// Creates a new simulator using the `*_hash_max_size` and
+    /// `*_hash_bucket_size` settings.
+    ///
+    /// nginx rounds the bucket size up to a multiple of the cache line size
+    /// before using it and so does this.
+    ///
+    pub fn new(max_size: usize, bucket_size: usize) -> Self {
+        NgxHashInit {
+            max_size,
+            bucket_size,
+            pointer_size: 8,
+            cacheline_size: 64,
+        }
+    }
+    ///
+    /// Sets the pointer size, 4 for 32 bit builds.
+    ///
+    pub fn pointer_size(mut self, pointer_size: usize) -> Self {
+        self.pointer_size = pointer_size.max(1);
+        self
+    }
+    ///
+    /// Sets `NGX_CPU_CACHE_LINE`, as found by `./configure`.
+    ///
+    pub fn cacheline_size(mut self, cacheline_size: usize) -> Self {
+        self.cacheline_size = cacheline_size.max(1);
+        self
+    }
+    ///
+    /// Simulates a table of exact names hashed with `ngx_hash_key_lc()`, like
+    /// the ones used for headers and variables.
+    ///
+    pub fn init<S: AsRef<[u8]>>(&self, names: &[S]) -> Result<NgxHashReport, NgxHashError> {
+        let keys: Vec<(&[u8], u64)> = names
+            .iter()
+            .map(|n| (n.as_ref(), ngx_hash_key_lc(n.as_ref())))
+            .collect();
+        self.simulate(&keys)
+    }
+    ///
+    /// Simulates every table nginx builds for the `server_name`s of a listen
+    /// address.
+    ///
+    /// Names that differ only in case are the same name and, like nginx does
+    /// after its "conflicting server name" warning, only the first is kept.
+    ///
+    pub fn server_names<S: AsRef<[u8]>>(
+        &self,
+        names: &[S],
+    ) -> Result<NgxServerNamesReport, NgxHashError> {
+        let mut exact: Vec<Vec<u8>> = Vec::new();
+        let mut head: Vec<Vec<u8>> = Vec::new();
+        let mut tail: Vec<Vec<u8>> = Vec::new();
+        for name in names {
+            let name = name.as_ref().to_ascii_lowercase();
+            let (list, key) = match wildcard(&name)? {
+                Wildcard::Exact => (&mut exact, name),
+                Wildcard::Head(skip) => (&mut head, reverse_labels(&name, skip)),
+                Wildcard::Tail => (&mut tail, name[..name.len() - 2].to_vec()),
+            };
+            if !list.contains(&key) {
+                list.push(key);
+            }
+        }
+        let exact = if exact.is_empty() {
+            None
+        } else {
+            Some(self.init(&exact)?)
+        };
+        Ok(NgxServerNamesReport {
+            exact,
+            head: self.wildcard_tables(head)?,
+            tail: self.wildcard_tables(tail)?,
+        })
+    }
+    ///
+    /// Returns the tables for one list of wildcard names.
+    ///
+    fn wildcard_tables(&self, mut names: Vec<Vec<u8>>) -> Result<Vec<NgxHashReport>, NgxHashError> {
+        let mut tables = Vec::new();
+        if !names.is_empty() {
+            names.sort_by(|a, b| dns_cmp(a, b));
+            let names: Vec<&[u8]> = names.iter().map(|n| &n[..]).collect();
+            self.wildcard_init(&names, &mut tables)?;
+        }
+        Ok(tables)
+    }
+    ///
+    /// Follows `ngx_hash_wildcard_init()`, building a table of the first
+    /// labels and recursing into the rest of the names under each label.
+    ///
+    fn wildcard_init(
+        &self,
+        names: &[&[u8]],
+        tables: &mut Vec<NgxHashReport>,
+    ) -> Result<(), NgxHashError> {
+        let mut labels: Vec<(&[u8], u64)> = Vec::new();
+        let mut n = 0;
+        while n < names.len() {
+            let name = names[n];
+            let (len, dot) = match name.iter().position(|&b| b == b'.') {
+                Some(len) => (len, true),
+                None => (name.len(), false),
+            };
+            labels.push((&name[..len], ngx_hash_key_lc(&name[..len])));
+            let prefix = if dot { len + 1 } else { len };
+            let mut next: Vec<&[u8]> = Vec::new();
+            if name.len() != prefix {
+                next.push(&name[prefix..]);
+            }
+            let mut i = n + 1;
+            while i < names.len() {
+                let other = names[i];
+                if other.len() < prefix || other[..prefix] != name[..prefix] {
+                    break;
+                }
+                if !dot && other.len() > len && other[len] != b'.' {
+                    break;
+                }
+                next.push(other.get(len + 1..).unwrap_or(&[]));
+                i += 1;
+            }
+            if !next.is_empty() {
+                self.wildcard_init(&next, tables)?;
+            }
+            n = i;
+        }
+        tables.push(self.simulate(&labels)?);
+        Ok(())
+    }
+    ///
+    /// Follows `ngx_hash_init()` for names with their hashes.
+    ///
+    fn simulate(&self, keys: &[(&[u8], u64)]) -> Result<NgxHashReport, NgxHashError> {
+        let ptr = self.pointer_size;
+        let limit = 65536 - self.cacheline_size;
+        let bucket_size = align(self.bucket_size, self.cacheline_size);
+        if self.max_size == 0 {
+            return Err(NgxHashError::MaxSizeZero);
+        }
+        if bucket_size > limit {
+            return Err(NgxHashError::BucketSizeTooBig);
+        }
+        let mut min_bucket_size = 0;
+        for &(name, _) in keys {
+            let needed = self.elt_size(name) + ptr;
+            min_bucket_size = min_bucket_size.max(needed);
+            if bucket_size < needed {
+                return Err(NgxHashError::BucketSizeTooSmall(name.to_vec(), needed));
+            }
+        }
+        // Every name needs at least 3 pointers, so this only catches tiny
+        // bucket sizes with no names, which would otherwise divide by zero.
+        if bucket_size < 3 * ptr {
+            return Err(NgxHashError::BucketSizeTooSmall(Vec::new(), 3 * ptr));
+        }
+        let usable = bucket_size - ptr;
+        let mut start = keys.len() / (usable / (2 * ptr));
+        if start == 0 {
+            start = 1;
+        }
+        if self.max_size > 10000 && !keys.is_empty() && self.max_size / keys.len() < 100 {
+            start = self.max_size - 1000;
+        }
+        let mut test = vec![0usize; self.max_size];
+        let mut found = None;
+        'sizes: for size in start..=self.max_size {
+            for t in &mut test[..size] {
+                *t = 0;
+            }
+            for &(name, hash) in keys {
+                let bucket = (hash % size as u64) as usize;
+                let len = test[bucket] + self.elt_size(name);
+                if len > usable {
+                    continue 'sizes;
+                }
+                test[bucket] = len;
+            }
+            found = Some(size);
+            break;
+        }
+        let (size, optimal) = match found {
+            Some(size) => (size, true),
+            None => (self.max_size, false),
+        };
+        for t in &mut test[..size] {
+            *t = ptr;
+        }
+        for &(name, hash) in keys {
+            let bucket = (hash % size as u64) as usize;
+            test[bucket] += self.elt_size(name);
+            if test[bucket] > limit {
+                return Err(NgxHashError::MaxSizeTooSmall);
+            }
+        }
+        let bytes = test[..size]
+            .iter()
+            .filter(|&&t| t != ptr)
+            .map(|&t| align(t, self.cacheline_size))
+            .sum();
+        Ok(NgxHashReport {
+            size,
+            optimal,
+            min_bucket_size,
+            bytes,
+        })
+    }
+    ///
+    /// `NGX_HASH_ELT_SIZE()`, the bytes a name takes in a bucket.
+    ///
+    fn elt_size(&self, name: &[u8]) -> usize {
+        self.pointer_size + align(name.len() + 2, self.pointer_size)
+    }
+}
+
+enum Wildcard {
+    Exact,
+    Head(usize),
+    Tail,
+}
+
+///
+/// Sorts a server name the way `ngx_hash_add_key()` does.
+///
+fn wildcard(name: &[u8]) -> Result<Wildcard, NgxHashError> {
+    let invalid = || NgxHashError::InvalidName(name.to_vec());
+    let stars = name.iter().filter(|&&b| b == b'*').count();
+    if stars > 1 || name.windows(2).any(|w| w == b"..") || name.contains(&0) {
+        return Err(invalid());
+    }
+    let len = name.len();
+    if len > 1 && name[0] == b'.' {
+        return Ok(Wildcard::Head(1));
+    }
+    if len > 2 && name.starts_with(b"*.") {
+        return Ok(Wildcard::Head(2));
+    }
+    if len > 2 && name.ends_with(b".*") {
+        return Ok(Wildcard::Tail);
+    }
+    if stars > 0 {
+        return Err(invalid());
+    }
+    Ok(Wildcard::Exact)
+}
+
+///
+/// Turns `*.example.com` into `com.example.` and `.example.com` into
+/// `com.example` like `ngx_hash_add_key()` does for head wildcards.
+///
+fn reverse_labels(name: &[u8], skip: usize) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(name.len());
+    for label in name[skip..].rsplit(|&b| b == b'.') {
+        out.extend_from_slice(label);
+        out.push(b'.');
+    }
+    if skip == 1 {
+        out.pop();
+    }
+    out
+}
+
+///
+/// `ngx_dns_strcmp()`, which sorts `.` before every other byte.
+///
+fn dns_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    let key = |&c: &u8| if c == b'.' { b' ' } else { c };
+    a.iter().map(key).cmp(b.iter().map(key))
+}
+
+fn align(value: usize, to: usize) -> usize {
+    (value + to - 1) / to * to
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let vectors: [(&[u8], u64, u64); 4] = [
+            (b"", 0, 0),
+            (b"Host", 2_255_304, 3_208_616),
+            (
+                b"example.com",
+                86_041_043_537_016_573,
+                86_041_043_537_016_573,
+            ),
+            (
+                b"www.Example.COM",
+                13_152_922_952_459_926_758,
+                13_179_151_057_643_344_166,
+            ),
+        ];
+        for &(input, exact, lower) in &vectors {
+            let mut sut = NgxHashKey::new();
+            sut.write(input);
+            assert_eq!(sut.finish(), exact);
+            assert_eq!(sut.finish_u32(), exact as u32);
+            let mut sut = NgxHashKeyLc::new();
+            sut.write(input);
+            assert_eq!(sut.finish(), lower);
+        }
+        let name = "X-Forwarded-For-ABCDEFGHIJKLMNOPQRSTUVWXYZ-".repeat(4);
+        let mut sut = NgxHashKeyLc::new();
+        sut.write(name.as_bytes());
+        let mut expected = NgxHashKey::new();
+        expected.write(name.to_ascii_lowercase().as_bytes());
+        assert_eq!(sut.finish(), expected.finish());
+    }
+
+    #[test]
+    fn it_does_size_tables_correctly() {
+        assert_eq!(
+            reverse_labels(b"*.example.com", 2),
+            b"com.example.".to_vec()
+        );
+        assert_eq!(reverse_labels(b".example.com", 1), b"com.example".to_vec());
+        let names: Vec<String> = (0..200).map(|i| format!("host{}.example.com", i)).collect();
+        // A bucket size of 100 is rounded up to 128.
+        let report = NgxHashInit::new(512, 100).init(&names).unwrap();
+        assert!(report.optimal);
+        assert_eq!(report.min_bucket_size, 40);
+        assert_eq!(report.size, 99);
+        let report = NgxHashInit::new(512, 128)
+            .pointer_size(4)
+            .init(&names)
+            .unwrap();
+        assert_eq!(report.size, 73);
+        let sut = NgxHashInit::new(512, 64);
+        let report = sut.init(&names).unwrap();
+        assert!(!report.optimal);
+        assert_eq!(report.size, 512);
+        assert_eq!(sut.init(&names[..20]).unwrap().size, 25);
+        match NgxHashInit::new(512, 32).cacheline_size(32).init(&names) {
+            Err(NgxHashError::BucketSizeTooSmall(_, 40)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            NgxHashInit::new(0, 64).init(&names).unwrap_err(),
+            NgxHashError::MaxSizeZero
+        );
+        assert_eq!(sut.init(&names[..0]).unwrap().size, 1);
+        assert_eq!(
+            NgxHashInit::new(512, 0).init(&names[..0]).unwrap_err(),
+            NgxHashError::BucketSizeTooSmall(Vec::new(), 24)
+        );
+        assert_eq!(
+            NgxHashInit::new(512, 8)
+                .cacheline_size(8)
+                .init(&names[..0])
+                .unwrap_err(),
+            NgxHashError::BucketSizeTooSmall(Vec::new(), 24)
+        );
+        let servers = [
+            "example.com",
+            "EXAMPLE.com",
+            "*.example.com",
+            ".example.org",
+            "*.mail.example.org",
+            "www.example.*",
+        ];
+        let report = sut.server_names(&servers).unwrap();
+        assert_eq!(report.exact.unwrap().size, 1);
+        assert_eq!(report.head.len(), 4);
+        assert_eq!(report.tail.len(), 2);
+        assert!(report.optimal());
+        assert_eq!(report.min_bucket_size(), 32);
+        for bad in &["*.example.*", "www.*.com", "a..b"] {
+            assert_eq!(
+                sut.server_names(&[bad]).unwrap_err(),
+                NgxHashError::InvalidName(bad.as_bytes().to_vec())
+            );
+        }
+    }
+}
@@ -0,0 +1,214 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{flag, Checkpoint, CheckpointError, Resumable};
+use super::HasherU32;
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements the `PERL_HASH` macro from Perl 5.6 and 5.8.0, the last versions before Perl 5.8.1
+/// moved to Bob Jenkins' one-at-a-time hash.
+///
+/// It is X33a starting from 0 in a 32 bit `U32` with a final hash + (hash >> 5) added when the hash
+/// is finished. Depending on the version the key was read as plain `char` or `unsigned char`. On
+/// x86 a plain `char` is signed, which changes the hash of bytes 0x80 and up, so new_signed() is
+/// there for data from those builds.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::perl_hash::*;
+/// let input = "hello";
+/// let mut hasher = PerlHash::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish(), 131058167u64);
+/// assert_eq!(hasher.finish_u32(), 131058167u32);
+/// ```
+///
+/// The final step does nothing for the "Ez" and "FY" collision of X33a:
+///
+/// ```rust
+/// # use std::hash::Hasher;
+/// # use djb_hash::perl_hash::*;
+/// let mut hasher1 = PerlHash::new();
+/// let mut hasher2 = PerlHash::new();
+/// hasher1.write(&"Ez".as_bytes());
+/// hasher2.write(&"FY".as_bytes());
+/// assert_eq!(hasher1.finish(), 2473u64);
+/// assert_eq!(hasher1.finish(), hasher2.finish());
+/// ```
+///
+pub struct PerlHash {
+    hash: u32,
+    salt: u32,
+    consumed: u64,
+    signed: bool,
+    int_encoding: IntEncoding,
+}
+
+impl PerlHash {
+    ///
+    /// Creates a new hash starting from zero that reads bytes as `unsigned char`.
+    ///
+    pub fn new() -> Self {
+        PerlHash {
+            hash: 0,
+            salt: 0,
+            consumed: 0,
+            signed: false,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Creates a new hash starting from zero that reads bytes as a signed `char`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::hash::Hasher;
+    /// use djb_hash::HasherU32;
+    /// use djb_hash::perl_hash::*;
+    /// let input = "café";
+    /// let mut hasher = PerlHash::new_signed();
+    /// hasher.write(&input.as_bytes());
+    /// assert_eq!(hasher.finish_u32(), 124782328u32);
+    /// ```
+    ///
+    pub fn new_signed() -> Self {
+        PerlHash {
+            hash: 0,
+            salt: 0,
+            consumed: 0,
+            signed: true,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for PerlHash {
+    ///
+    /// Same as new(), reading bytes as `unsigned char`.
+    ///
+    fn default() -> Self {
+        PerlHash::new()
+    }
+}
+
+impl HasherU32 for PerlHash {
+    fn finish_u32(&self) -> u32 {
+        self.hash.wrapping_add(self.hash >> 5)
+    }
+}
+
+impl WriteIdentity for PerlHash {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 5).wrapping_add(self.hash).wrapping_add(value as u32);
+    }
+}
+
+impl Hasher for PerlHash {
+    ///
+    /// Returns the finished hash with the final hash + (hash >> 5) step done.
+    ///
+    fn finish(&self) -> u64 {
+        self.finish_u32() as u64
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does hash * 33 + byte but is implemented as hash << 5 (*32) + hash + byte as this is faster
+    /// on most processors vs normal multiplication.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        for byte in bytes {
+            let byte = if self.signed { *byte as i8 as u32 } else { *byte as u32 };
+            self.hash = (self.hash << 5).wrapping_add(self.hash).wrapping_add(byte);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for PerlHash {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            extra: vec![self.signed as u8],
+            ..Checkpoint::new(Algorithm::PerlHash, self.salt as u64, self.hash as u64, self.consumed, self.int_encoding)
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        let extra = checkpoint.expect(Algorithm::PerlHash, 1)?;
+        Ok(PerlHash {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            consumed: checkpoint.consumed,
+            signed: flag(extra[0])?,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let vectors: [(&[u8], u32, u32); 5] = [
+            (b"", 0, 0),
+            (b"a", 100, 100),
+            (b"abcdefghijklmnopqrstuvwxyz", 2660912164, 2660912164),
+            (b"caf\xc3\xa9", 124791304, 124782328),
+            (b"\xff\xfe", 8939, 134217691),
+        ];
+        for &(input, unsigned, signed) in vectors.iter() {
+            let mut sut = PerlHash::new();
+            sut.write(input);
+            assert_eq!(sut.finish_u32(), unsigned);
+            let mut sut = PerlHash::new_signed();
+            sut.write(input);
+            assert_eq!(sut.finish(), signed as u64);
+        }
+    }
+}
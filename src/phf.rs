@@ -0,0 +1,608 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Minimal perfect hash builder using salted X33aU32 hashes.
+//!
+//! This uses the hash and displace method. Every key is first put in a bucket
+//! using X33aU32 with a seed salt. Then, starting with the fullest bucket, a
+//! displacement salt is searched for that puts every key of the bucket into a
+//! slot no other key is using yet. Only the seed and one 32 bit displacement
+//! per bucket need to be kept, with about four keys per bucket by default.
+//!
+//! A lookup hashes the key twice and does a single compare against the key
+//! stored in the slot so keys that were not in the original set are always
+//! rejected.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::phf::*;
+//! let keywords = ["fn", "let", "match", "mod", "pub", "use"];
+//! let phf = Phf::build(&keywords).unwrap();
+//! assert_eq!(phf.get(b"match"), Some(2));
+//! assert_eq!(phf.get(b"loop"), None);
+//! ```
+//!
+//! The table can also be turned into Rust source for a static lookup so the
+//! search only has to be done once:
+//!
+//! ```rust
+//! # use djb_hash::phf::*;
+//! let phf = Phf::build(&["fn", "let"]).unwrap();
+//! let source = phf.to_rust_source("KEYWORDS");
+//! assert!(source.contains("pub static KEYWORDS: ::djb_hash::phf::PhfTable<'static>"));
+//! ```
+//!
+//! For maps that are known at compile time the `djb_phf_map!` macro in the
+//! djb_hash_macros crate builds a `PhfMap` instead.
+//!
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hasher;
+use x33a_u32::X33aU32;
+use HasherU32;
+
+///
+/// Borrowed form of a finished table that can be used in a `static`.
+///
+/// This is what the source from `Phf::to_rust_source()` builds. The keys are
+/// in slot order so `get()` returns the slot number of the key.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PhfTable<'a> {
+    pub seed: u32,
+    pub displacements: &'a [u32],
+    pub keys: &'a [&'a [u8]],
+}
+
+impl<'a> PhfTable<'a> {
+    ///
+    /// Returns the slot of the key or None if the key is not in the table.
+    ///
+    pub fn get(&self, key: &[u8]) -> Option<usize> {
+        let slot = slot(self.seed, self.displacements, self.keys.len(), key)?;
+        if self.keys[slot] == key {
+            Some(slot)
+        } else {
+            None
+        }
+    }
+}
+
+///
+/// Settings used while searching for a perfect hash.
+///
+#[derive(Clone, Debug)]
+pub struct PhfBuilder {
+    keys_per_bucket: usize,
+    seeds: u32,
+    displacements: u32,
+}
+
+impl PhfBuilder {
+    ///
+    /// Creates a new builder with the default settings.
+    ///
+    /// The defaults are about four keys per bucket, up to 16 seeds and up to
+    /// 64K displacements for each bucket.
+    ///
+    pub fn new() -> Self {
+        PhfBuilder {
+            keys_per_bucket: 4,
+            seeds: 16,
+            displacements: 1 << 16,
+        }
+    }
+    ///
+    /// Sets the average number of keys per bucket.
+    ///
+    /// Larger values make a smaller displacement table but take longer to
+    /// search.
+    ///
+    pub fn keys_per_bucket(mut self, keys_per_bucket: usize) -> Self {
+        assert!(keys_per_bucket > 0, "Need at least one key per bucket");
+        self.keys_per_bucket = keys_per_bucket;
+        self
+    }
+    ///
+    /// Sets how many seed salts are tried before giving up.
+    ///
+    pub fn seeds(mut self, seeds: u32) -> Self {
+        self.seeds = seeds;
+        self
+    }
+    ///
+    /// Sets how many displacement salts are tried for each bucket before
+    /// giving up on the current seed.
+    ///
+    pub fn displacements(mut self, displacements: u32) -> Self {
+        self.displacements = displacements;
+        self
+    }
+    ///
+    /// Searches for a minimal perfect hash of the keys.
+    ///
+    /// Keys that hash the same with X33aU32 and have the same length hash the
+    /// same with every salt, "Ez" and "FY" being one example, so no perfect
+    /// hash can be built when they are both in the set. These are reported as
+    /// `PhfError::Clash` before any searching is done.
+    ///
+    pub fn build<K: AsRef<[u8]>>(&self, keys: &[K]) -> Result<Phf, PhfError> {
+        let keys: Vec<&[u8]> = keys.iter().map(|k| k.as_ref()).collect();
+        check_keys(&keys)?;
+        let buckets = ((keys.len() + self.keys_per_bucket - 1) / self.keys_per_bucket).max(1);
+        let mut last_failed = Vec::new();
+        for seed in (0..self.seeds).map(|i| 5381u32.wrapping_add(i)) {
+            match self.try_seed(&keys, buckets, seed) {
+                Ok(phf) => return Ok(phf),
+                Err(failed) => last_failed = failed,
+            }
+        }
+        Err(PhfError::NotFound(
+            last_failed.iter().map(|&i| keys[i].to_vec()).collect(),
+        ))
+    }
+    ///
+    /// Tries to place every key using the given seed. On failure returns the
+    /// indexes of the keys in the bucket that could not be placed.
+    ///
+    fn try_seed(&self, keys: &[&[u8]], buckets: usize, seed: u32) -> Result<Phf, Vec<usize>> {
+        let n = keys.len();
+        let mut members = vec![Vec::new(); buckets];
+        for (i, key) in keys.iter().enumerate() {
+            members[(hash(seed, key) % buckets as u32) as usize].push(i);
+        }
+        let mut order: Vec<usize> = (0..buckets).collect();
+        order.sort_by(|&a, &b| members[b].len().cmp(&members[a].len()));
+        let mut displacements = vec![0u32; buckets];
+        let mut slots: Vec<Option<usize>> = vec![None; n];
+        let mut candidate = Vec::new();
+        for bucket in order {
+            if members[bucket].is_empty() {
+                break;
+            }
+            let found = (0..self.displacements).find(|&d| {
+                candidate.clear();
+                for &i in &members[bucket] {
+                    let slot = (hash(d, keys[i]) % n as u32) as usize;
+                    if slots[slot].is_some() || candidate.contains(&slot) {
+                        return false;
+                    }
+                    candidate.push(slot);
+                }
+                true
+            });
+            match found {
+                Some(d) => {
+                    displacements[bucket] = d;
+                    for (&slot, &i) in candidate.iter().zip(&members[bucket]) {
+                        slots[slot] = Some(i);
+                    }
+                }
+                None => return Err(members[bucket].clone()),
+            }
+        }
+        let order: Vec<usize> = slots.into_iter().map(|i| i.unwrap()).collect();
+        Ok(Phf {
+            seed,
+            displacements,
+            keys: order.iter().map(|&i| keys[i].to_vec()).collect(),
+            order,
+        })
+    }
+}
+
+impl Default for PhfBuilder {
+    fn default() -> Self {
+        PhfBuilder::new()
+    }
+}
+
+///
+/// A finished minimal perfect hash over a set of keys.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Phf {
+    seed: u32,
+    displacements: Vec<u32>,
+    keys: Vec<Vec<u8>>,
+    order: Vec<usize>,
+}
+
+impl Phf {
+    ///
+    /// Searches for a minimal perfect hash of the keys using the default
+    /// settings.
+    ///
+    pub fn build<K: AsRef<[u8]>>(keys: &[K]) -> Result<Phf, PhfError> {
+        PhfBuilder::new().build(keys)
+    }
+    ///
+    /// Returns the index of the key in the set given to build() or None if
+    /// the key was not in the set.
+    ///
+    pub fn get(&self, key: &[u8]) -> Option<usize> {
+        self.slot(key).map(|slot| self.order[slot])
+    }
+    ///
+    /// Returns the slot of the key or None if the key was not in the set.
+    ///
+    pub fn slot(&self, key: &[u8]) -> Option<usize> {
+        let slot = slot(self.seed, &self.displacements, self.keys.len(), key)?;
+        if self.keys[slot] == key {
+            Some(slot)
+        } else {
+            None
+        }
+    }
+    ///
+    /// Returns the salt used to pick the bucket of a key.
+    ///
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+    ///
+    /// Returns the displacement salt of every bucket.
+    ///
+    pub fn displacements(&self) -> &[u32] {
+        &self.displacements
+    }
+    ///
+    /// Returns the keys in slot order.
+    ///
+    pub fn keys(&self) -> &[Vec<u8>] {
+        &self.keys
+    }
+    ///
+    /// Returns the original index of the key in each slot.
+    ///
+    /// Use this to put any values that go with the keys into slot order.
+    ///
+    pub fn order(&self) -> &[usize] {
+        &self.order
+    }
+    ///
+    /// Generates Rust source for a `PhfTable` static with the given name.
+    ///
+    pub fn to_rust_source(&self, name: &str) -> String {
+        let displacements: Vec<String> = self.displacements.iter().map(|d| d.to_string()).collect();
+        let mut out = String::new();
+        out.push_str("// Generated by djb_hash::phf. Do not edit.\n");
+        out.push_str(&format!(
+            "pub static {}: ::djb_hash::phf::PhfTable<'static> = ::djb_hash::phf::PhfTable {{\n",
+            name
+        ));
+        out.push_str(&format!("    seed: {},\n", self.seed));
+        out.push_str(&format!(
+            "    displacements: &[{}],\n",
+            displacements.join(", ")
+        ));
+        out.push_str("    keys: &[\n");
+        for key in &self.keys {
+            out.push_str(&format!("        b\"{}\",\n", escape(key)));
+        }
+        out.push_str("    ],\n};\n");
+        out
+    }
+}
+
+///
+/// Map built at compile time by the `djb_phf_map!` macro in the
+/// djb_hash_macros crate.
+///
+/// Unlike `Phf` this is not minimal. The entries table has empty slots but a
+/// lookup is only a single salted X33aU32 hash of the key, one probe and one
+/// compare.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PhfMap<'a, V: 'a> {
+    pub salt: u32,
+    pub entries: &'a [Option<(&'a str, V)>],
+}
+
+impl<'a, V> PhfMap<'a, V> {
+    ///
+    /// Returns the value of the key or None if the key is not in the map.
+    ///
+    pub fn get(&self, key: &str) -> Option<&V> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        let mut hasher = X33aU32::new_with_salt(self.salt);
+        hasher.write(key.as_bytes());
+        match self.entries[(hasher.finish_u32() % self.entries.len() as u32) as usize] {
+            Some((k, ref v)) if k == key => Some(v),
+            _ => None,
+        }
+    }
+    ///
+    /// Returns true if the key is in the map.
+    ///
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+    ///
+    /// Returns the number of keys in the map.
+    ///
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|e| e.is_some()).count()
+    }
+    ///
+    /// Returns true if the map has no keys.
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    ///
+    /// Iterates over the keys and values in table order.
+    ///
+    pub fn iter(&self) -> impl Iterator<Item = (&'a str, &V)> {
+        self.entries
+            .iter()
+            .filter_map(|e| e.as_ref().map(|&(k, ref v)| (k, v)))
+    }
+}
+
+///
+/// Salt and table size where X33aU32 alone puts every key in its own slot.
+///
+/// This is what `djb_phf_map!` uses to lay out its table.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaltTable {
+    pub salt: u32,
+    ///
+    /// Index of the key in each slot of the table.
+    ///
+    pub slots: Vec<Option<usize>>,
+}
+
+impl SaltTable {
+    ///
+    /// Searches table sizes from the number of keys up to `max_size`, trying
+    /// `salts` salts starting from 5381 for each size, and returns the first
+    /// collision free one.
+    ///
+    /// When nothing is found the error holds the keys that still collided in
+    /// the closest attempt.
+    ///
+    pub fn search<K: AsRef<[u8]>>(
+        keys: &[K],
+        salts: u32,
+        max_size: usize,
+    ) -> Result<SaltTable, PhfError> {
+        let keys: Vec<&[u8]> = keys.iter().map(|k| k.as_ref()).collect();
+        check_keys(&keys)?;
+        if keys.is_empty() {
+            return Ok(SaltTable {
+                salt: 5381,
+                slots: Vec::new(),
+            });
+        }
+        let mut best: Option<Vec<usize>> = None;
+        for size in keys.len()..=max_size.max(keys.len()) {
+            for salt in (0..salts).map(|i| 5381u32.wrapping_add(i)) {
+                let mut slots = vec![None; size];
+                let mut clashing = Vec::new();
+                for (i, key) in keys.iter().enumerate() {
+                    let mut hasher = X33aU32::new_with_salt(salt);
+                    hasher.write(key);
+                    let slot = &mut slots[(hasher.finish_u32() % size as u32) as usize];
+                    match *slot {
+                        Some(j) => {
+                            clashing.push(j);
+                            clashing.push(i);
+                        }
+                        None => *slot = Some(i),
+                    }
+                }
+                if clashing.is_empty() {
+                    return Ok(SaltTable { salt, slots });
+                }
+                if best.as_ref().map_or(true, |b| clashing.len() < b.len()) {
+                    best = Some(clashing);
+                }
+            }
+        }
+        let mut clashing = best.unwrap_or_default();
+        clashing.sort_unstable();
+        clashing.dedup();
+        Err(PhfError::NotFound(
+            clashing.iter().map(|&i| keys[i].to_vec()).collect(),
+        ))
+    }
+}
+
+///
+/// Reasons a perfect hash could not be built.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PhfError {
+    ///
+    /// The same key was given more than once.
+    ///
+    DuplicateKey(Vec<u8>),
+    ///
+    /// The keys have the same length and X33aU32 hash so will collide with
+    /// every salt.
+    ///
+    Clash(Vec<Vec<u8>>),
+    ///
+    /// No seed and displacement could be found within the search limits. Holds
+    /// the keys of the bucket that could not be placed.
+    ///
+    NotFound(Vec<Vec<u8>>),
+}
+
+impl fmt::Display for PhfError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PhfError::DuplicateKey(ref key) => {
+                write!(f, "duplicate key {:?}", String::from_utf8_lossy(key))
+            }
+            PhfError::Clash(ref keys) => {
+                write!(f, "keys {} collide for every salt", quoted(keys))
+            }
+            PhfError::NotFound(ref keys) => write!(
+                f,
+                "no perfect hash found within the search limits, could not place keys {}",
+                quoted(keys)
+            ),
+        }
+    }
+}
+
+impl Error for PhfError {}
+
+fn check_keys(keys: &[&[u8]]) -> Result<(), PhfError> {
+    let mut seen: HashMap<(usize, u32), Vec<&[u8]>> = HashMap::new();
+    for key in keys {
+        let mut hasher = X33aU32::new();
+        hasher.write(key);
+        let same = seen.entry((key.len(), hasher.finish_u32())).or_default();
+        if same.contains(key) {
+            return Err(PhfError::DuplicateKey(key.to_vec()));
+        }
+        same.push(key);
+    }
+    let mut clashes: Vec<&Vec<&[u8]>> = seen.values().filter(|same| same.len() > 1).collect();
+    clashes.sort();
+    match clashes.first() {
+        Some(same) => Err(PhfError::Clash(same.iter().map(|k| k.to_vec()).collect())),
+        None => Ok(()),
+    }
+}
+
+///
+/// Hashes the key with X33aU32 and then runs the MurmurHash3 finalizer over it.
+///
+/// Changing the X33aU32 salt only adds the same amount to the hash of every
+/// key with the same length, so without the extra mixing those keys would move
+/// around the table in a fixed pattern and the displacement search would
+/// rarely find room for them.
+///
+fn hash(salt: u32, key: &[u8]) -> u32 {
+    let mut hasher = X33aU32::new_with_salt(salt);
+    hasher.write(key);
+    let mut h = hasher.finish_u32();
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^ (h >> 16)
+}
+
+fn slot(seed: u32, displacements: &[u32], n: usize, key: &[u8]) -> Option<usize> {
+    if n == 0 || displacements.is_empty() {
+        return None;
+    }
+    let bucket = hash(seed, key) % displacements.len() as u32;
+    Some((hash(displacements[bucket as usize], key) % n as u32) as usize)
+}
+
+fn escape(key: &[u8]) -> String {
+    key.iter()
+        .flat_map(|&b| ::std::ascii::escape_default(b))
+        .map(|b| b as char)
+        .collect()
+}
+
+fn quoted(keys: &[Vec<u8>]) -> String {
+    let quoted: Vec<String> = keys
+        .iter()
+        .map(|k| format!("{:?}", String::from_utf8_lossy(k)))
+        .collect();
+    quoted.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_does_build_correctly() {
+        let keys: Vec<String> = (0..1024).map(|i| format!("key{}", i)).collect();
+        let sut = Phf::build(&keys).unwrap();
+        assert_eq!(sut.displacements().len(), 256);
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(sut.get(key.as_bytes()), Some(i));
+        }
+        assert_eq!(sut.get(b"key1024"), None);
+        assert_eq!(sut.get(b""), None);
+        let keys: Vec<&[u8]> = sut.keys().iter().map(|k| k.as_slice()).collect();
+        let table = PhfTable {
+            seed: sut.seed(),
+            displacements: sut.displacements(),
+            keys: &keys,
+        };
+        assert_eq!(table.get(b"key42"), sut.slot(b"key42"));
+        assert_eq!(table.get(b"nope"), None);
+        let empty = Phf::build(&[] as &[&str]).unwrap();
+        assert_eq!(empty.get(b"key"), None);
+        assert_eq!(
+            Phf::build(&["a", "b", "a"]),
+            Err(PhfError::DuplicateKey(b"a".to_vec()))
+        );
+        assert_eq!(
+            Phf::build(&["abc", "Ez", "FY"]),
+            Err(PhfError::Clash(vec![b"Ez".to_vec(), b"FY".to_vec()]))
+        );
+        let keys = ["GET", "HEAD", "POST", "PUT", "DELETE"];
+        let table = SaltTable::search(&keys, 64, 20).unwrap();
+        let entries: Vec<Option<(&str, usize)>> = table
+            .slots
+            .iter()
+            .map(|s| s.map(|i| (keys[i], i)))
+            .collect();
+        let map = PhfMap {
+            salt: table.salt,
+            entries: &entries,
+        };
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get("POST"), Some(&2));
+        assert_eq!(map.get("PATCH"), None);
+        assert_eq!(
+            SaltTable::search(&["Ez", "FY"], 64, 20),
+            Err(PhfError::Clash(vec![b"Ez".to_vec(), b"FY".to_vec()]))
+        );
+        let source = Phf::build(&["a\"b"]).unwrap().to_rust_source("T");
+        assert_eq!(
+            source,
+            "// Generated by djb_hash::phf. Do not edit.\n\
+             pub static T: ::djb_hash::phf::PhfTable<'static> = ::djb_hash::phf::PhfTable {\n    \
+             seed: 5381,\n    \
+             displacements: &[0],\n    \
+             keys: &[\n        \
+             b\"a\\\"b\",\n    \
+             ],\n\
+             };\n"
+        );
+    }
+}
@@ -0,0 +1,267 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Emulation of the open addressing used by Python 2 dicts.
+//!
+//! Python 2 dicts are tables of a power of two size. A key starts at
+//! `hash & mask` and each collision moves it to `(5 * i + perturb + 1) & mask`
+//! where `perturb` starts as the hash and is shifted right 5 bits each step,
+//! so all the bits of the hash get used sooner or later. The order keys end up
+//! in the table is the order a Python 2 dict iterates them in, which is what
+//! shows up in things like pickles and `repr()` output.
+//!
+//! `perturb` is a C `size_t`, so the hash is sign extended to it and the
+//! simulators need to know if that is 64 bits, the default, or 32 bits.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use djb_hash::py2_dict::*;
+//! use djb_hash::py2_str_hash::*;
+//! let mut dict = Py2Dict::new();
+//! for key in &["a", "b", "c", "d", "e", "f"] {
+//!     let mut hasher = Py2StrHash::new();
+//!     hasher.write(key.as_bytes());
+//!     dict.insert(hasher.finish_signed());
+//! }
+//! // Same as {"a": 1, ...}.keys() in Python 2.
+//! assert_eq!(dict.order(), vec![0, 2, 1, 4, 3, 5]);
+//! ```
+//!
+
+///
+/// `PyDict_MINSIZE`, the size of a new dict table.
+///
+pub const PY_DICT_MINSIZE: usize = 8;
+
+const PERTURB_SHIFT: u32 = 5;
+
+///
+/// Iterator over the table slots Python 2 tries for a hash.
+///
+/// The slots never run out, as in Python, so something like take() or find()
+/// needs to be used on it.
+///
+/// # Examples
+///
+/// ```rust
+/// use djb_hash::py2_dict::*;
+/// let slots: Vec<usize> = DictProbe::new(-2, 8).take(4).collect();
+/// assert_eq!(slots, vec![6, 5, 1, 5]);
+/// ```
+///
+#[derive(Clone, Debug)]
+pub struct DictProbe {
+    slot: u64,
+    perturb: u64,
+    mask: u64,
+    started: bool,
+}
+
+impl DictProbe {
+    ///
+    /// Creates a new probe for a hash in a table of `table_size` slots, which
+    /// needs to be a power of two.
+    ///
+    pub fn new(hash: i64, table_size: usize) -> Self {
+        DictProbe {
+            slot: hash as u64,
+            perturb: hash as u64,
+            mask: table_size.wrapping_sub(1) as u64,
+            started: false,
+        }
+    }
+    ///
+    /// Makes `perturb` a 32 bit `size_t` for 32 bit builds of Python.
+    ///
+    pub fn size_t_32(mut self) -> Self {
+        self.perturb &= 0xffff_ffff;
+        self
+    }
+}
+
+impl Iterator for DictProbe {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.started {
+            self.slot = (self.slot << 2)
+                .wrapping_add(self.slot)
+                .wrapping_add(self.perturb)
+                .wrapping_add(1);
+            self.perturb >>= PERTURB_SHIFT;
+        }
+        self.started = true;
+        Some((self.slot & self.mask) as usize)
+    }
+}
+
+///
+/// Simulates the table of a Python 2 dict as keys are added to it.
+///
+/// Only the hashes are kept, so every insert is taken to be a new key.
+/// Deletes are not simulated.
+///
+#[derive(Clone, Debug)]
+pub struct Py2Dict {
+    hashes: Vec<i64>,
+    table: Vec<Option<usize>>,
+    size_t_32: bool,
+}
+
+impl Py2Dict {
+    ///
+    /// Creates a new empty dict for a build with a 64 bit `size_t`.
+    ///
+    pub fn new() -> Self {
+        Py2Dict {
+            hashes: Vec::new(),
+            table: vec![None; PY_DICT_MINSIZE],
+            size_t_32: false,
+        }
+    }
+    ///
+    /// Creates a new empty dict for a build with a 32 bit `size_t`.
+    ///
+    pub fn new_size_t_32() -> Self {
+        Py2Dict {
+            size_t_32: true,
+            ..Py2Dict::new()
+        }
+    }
+    ///
+    /// Adds a key by its hash and returns the number of the key.
+    ///
+    /// Like `PyDict_SetItem()` the table is resized once it is two thirds
+    /// full, to the smallest power of two over four times the number of keys,
+    /// or two times for dicts of over 50000 keys.
+    ///
+    pub fn insert(&mut self, hash: i64) -> usize {
+        let key = self.hashes.len();
+        self.hashes.push(hash);
+        self.place(key);
+        let used = self.hashes.len();
+        if used * 3 >= self.table.len() * 2 {
+            self.resize(if used > 50000 { 2 } else { 4 } * used);
+        }
+        key
+    }
+    ///
+    /// Returns the table with the number of the key in each used slot.
+    ///
+    pub fn slots(&self) -> &[Option<usize>] {
+        &self.table
+    }
+    ///
+    /// Returns the numbers of the keys in the order Python 2 iterates them.
+    ///
+    pub fn order(&self) -> Vec<usize> {
+        self.table.iter().filter_map(|&key| key).collect()
+    }
+    ///
+    /// Returns the probe sequence for a hash in the current table.
+    ///
+    pub fn probe(&self, hash: i64) -> DictProbe {
+        let probe = DictProbe::new(hash, self.table.len());
+        if self.size_t_32 {
+            probe.size_t_32()
+        } else {
+            probe
+        }
+    }
+    fn place(&mut self, key: usize) {
+        let mut probe = self.probe(self.hashes[key]);
+        let slot = probe
+            .find(|&slot| self.table[slot].is_none())
+            .expect("dict table always has a free slot");
+        self.table[slot] = Some(key);
+    }
+    ///
+    /// `dictresize()`, which puts the keys back in old table order.
+    ///
+    fn resize(&mut self, min_used: usize) {
+        let mut size = PY_DICT_MINSIZE;
+        while size <= min_used {
+            size <<= 1;
+        }
+        let old = std::mem::replace(&mut self.table, vec![None; size]);
+        for key in old.into_iter().flatten() {
+            self.place(key);
+        }
+    }
+}
+
+impl Default for Py2Dict {
+    ///
+    /// Same as new(), for a 64 bit `size_t`.
+    ///
+    fn default() -> Self {
+        Py2Dict::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use py2_str_hash::Py2StrHash;
+    use py2_str_hash_u32::Py2StrHashU32;
+    use std::hash::Hasher;
+
+    #[test]
+    fn it_does_probe_correctly() {
+        let keys = [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+        ];
+        let mut sut = Py2Dict::new();
+        for key in &keys {
+            let mut hasher = Py2StrHash::new();
+            hasher.write(key.as_bytes());
+            sut.insert(hasher.finish_signed());
+        }
+        // The order of .keys() in Python 2.7.18 on x86_64 Linux.
+        let expected = [
+            "seven", "ten", "nine", "six", "three", "two", "four", "five", "eight", "one",
+        ];
+        let order: Vec<&str> = sut.order().iter().map(|&k| keys[k]).collect();
+        assert_eq!(order, expected);
+        assert_eq!(sut.slots().len(), 32);
+        let mut sut = Py2Dict::new_size_t_32();
+        for key in &keys[..5] {
+            let mut hasher = Py2StrHashU32::new();
+            hasher.write(key.as_bytes());
+            sut.insert(hasher.finish_signed() as i64);
+        }
+        assert_eq!(sut.order().len(), 5);
+        let probe: Vec<usize> = DictProbe::new(-1, 8).size_t_32().take(10).collect();
+        assert_eq!(probe, vec![7, 3, 7, 3, 7, 3, 7, 7, 4, 5]);
+        let probe: Vec<usize> = DictProbe::new(-1, 8).skip(7).take(3).collect();
+        assert_eq!(probe, vec![3, 7, 3]);
+    }
+}
@@ -0,0 +1,216 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{Checkpoint, CheckpointError, Resumable};
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements the 64 bit version of the Python 2 `str.__hash__()`, as found on Python 2 builds where a C `long` is 64 bits, like Linux and macOS on x86_64.
+///
+/// It starts from the first byte shifted left 7 bits, then for each byte multiplies by 1000003
+/// and XORs in the byte, much like X33x does with 33. At the end the length is XORed in. Python
+/// reserves -1 for errors so a hash of -1 becomes -2 and the empty string always hashes to 0.
+///
+/// finish() gives the bits of the hash as unsigned while finish_signed() gives the value
+/// `hash()` shows in Python.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::py2_str_hash::*;
+/// let input = "hello";
+/// let mut hasher = Py2StrHash::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish(), 840651671246116861u64);
+/// assert_eq!(hasher.finish_signed(), 840651671246116861i64);
+/// ```
+///
+/// Another example:
+///
+/// ```rust
+/// # use std::hash::Hasher;
+/// # use djb_hash::py2_str_hash::*;
+/// let input = "hello world";
+/// let mut hasher = Py2StrHash::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish_signed(), -2324238377118044897i64);
+/// ```
+///
+pub struct Py2StrHash {
+    hash: u64,
+    salt: u64,
+    len: u64,
+    suffix: u64,
+    int_encoding: IntEncoding,
+}
+
+impl Py2StrHash {
+    ///
+    /// Creates a new hash the same as Python 2 without hash randomization.
+    ///
+    pub fn new() -> Self {
+        Py2StrHash::new_with_secret(0, 0)
+    }
+    ///
+    /// Creates a new hash using the prefix and suffix of `_Py_HashSecret`.
+    ///
+    /// Python 2.7.3 and later mix these in when started with `-R` or with
+    /// `PYTHONHASHSEED` set, otherwise both are 0.
+    ///
+    pub fn new_with_secret(prefix: u64, suffix: u64) -> Self {
+        Py2StrHash {
+            hash: prefix,
+            salt: prefix,
+            len: 0,
+            suffix,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Returns the hash as the signed value Python 2 gives.
+    ///
+    pub fn finish_signed(&self) -> i64 {
+        self.finish_u64() as i64
+    }
+    fn finish_u64(&self) -> u64 {
+        if self.len == 0 {
+            return 0;
+        }
+        match self.hash ^ self.len ^ self.suffix {
+            u64::MAX => u64::MAX - 1,
+            hash => hash,
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for Py2StrHash {
+    ///
+    /// Same as new(), without hash randomization.
+    ///
+    fn default() -> Self {
+        Py2StrHash::new()
+    }
+}
+
+impl WriteIdentity for Py2StrHash {
+    const BITS: u32 = 64;
+    fn write_identity(&mut self, value: u64) {
+        if self.len == 0 {
+            self.hash ^= value << 7;
+        }
+        self.hash = self.hash.wrapping_mul(1000003) ^ value;
+        self.len = self.len.wrapping_add(1);
+    }
+}
+
+impl Hasher for Py2StrHash {
+    fn finish(&self) -> u64 {
+        self.finish_u64()
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does (hash * 1000003) XOR byte, after first XORing in the first byte shifted left 7 bits.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.len == 0 {
+                self.hash ^= (*byte as u64) << 7;
+            }
+            self.hash = self.hash.wrapping_mul(1000003) ^ *byte as u64;
+            self.len = self.len.wrapping_add(1);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for Py2StrHash {
+    ///
+    /// The salt is the prefix of the secret and the length is what has been consumed.
+    ///
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            extra: self.suffix.to_le_bytes().to_vec(),
+            ..Checkpoint::new(Algorithm::Py2StrHash, self.salt, self.hash, self.len, self.int_encoding)
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        let extra = checkpoint.expect(Algorithm::Py2StrHash, 8)?;
+        let mut suffix = [0u8; 8];
+        suffix.copy_from_slice(extra);
+        Ok(Py2StrHash {
+            hash: checkpoint.state,
+            salt: checkpoint.salt,
+            len: checkpoint.consumed,
+            suffix: u64::from_le_bytes(suffix),
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        // Values from hash() in Python 2.7.18 on x86_64 Linux.
+        let vectors: [(&[u8], i64); 6] = [
+            (b"", 0),
+            (b"a", 12416037344),
+            (b"Ez", 8832053061079799),
+            (b"python", -9095698888683775416),
+            (b"\xff\x00", 32640195839293759),
+            (b"naive caf\xc3\xa9", 5041814807178267970),
+        ];
+        for &(input, expected) in vectors.iter() {
+            let mut sut = Py2StrHash::new();
+            sut.write(input);
+            assert_eq!(sut.finish_signed(), expected);
+            assert_eq!(sut.finish(), expected as u64);
+        }
+        let mut sut = Py2StrHash::new();
+        sut.write(b"hello ");
+        sut.write(b"world");
+        assert_eq!(sut.finish_signed(), -2324238377118044897);
+    }
+}
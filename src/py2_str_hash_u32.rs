@@ -0,0 +1,214 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{Checkpoint, CheckpointError, Resumable};
+use super::HasherU32;
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements the 32 bit version of the Python 2 `str.__hash__()`, as found on Python 2 builds where a C `long` is 32 bits, like any 32 bit build and 64 bit Windows.
+///
+/// It starts from the first byte shifted left 7 bits, then for each byte multiplies by 1000003
+/// and XORs in the byte, much like X33x does with 33. At the end the length is XORed in. Python
+/// reserves -1 for errors so a hash of -1 becomes -2 and the empty string always hashes to 0.
+///
+/// finish() gives the bits of the hash as unsigned while finish_signed() gives the value
+/// `hash()` shows in Python.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::py2_str_hash_u32::*;
+/// let input = "a";
+/// let mut hasher = Py2StrHashU32::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish_u32(), 3826102752u32);
+/// assert_eq!(hasher.finish_signed(), -468864544i32);
+/// ```
+///
+pub struct Py2StrHashU32 {
+    hash: u32,
+    salt: u32,
+    len: u32,
+    suffix: u32,
+    int_encoding: IntEncoding,
+}
+
+impl Py2StrHashU32 {
+    ///
+    /// Creates a new hash the same as Python 2 without hash randomization.
+    ///
+    pub fn new() -> Self {
+        Py2StrHashU32::new_with_secret(0, 0)
+    }
+    ///
+    /// Creates a new hash using the prefix and suffix of `_Py_HashSecret`.
+    ///
+    /// Python 2.7.3 and later mix these in when started with `-R` or with
+    /// `PYTHONHASHSEED` set, otherwise both are 0.
+    ///
+    pub fn new_with_secret(prefix: u32, suffix: u32) -> Self {
+        Py2StrHashU32 {
+            hash: prefix,
+            salt: prefix,
+            len: 0,
+            suffix,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Returns the hash as the signed value Python 2 gives.
+    ///
+    pub fn finish_signed(&self) -> i32 {
+        self.finish_u32() as i32
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for Py2StrHashU32 {
+    ///
+    /// Same as new(), without hash randomization.
+    ///
+    fn default() -> Self {
+        Py2StrHashU32::new()
+    }
+}
+
+impl HasherU32 for Py2StrHashU32 {
+    fn finish_u32(&self) -> u32 {
+        if self.len == 0 {
+            return 0;
+        }
+        match self.hash ^ self.len ^ self.suffix {
+            u32::MAX => u32::MAX - 1,
+            hash => hash,
+        }
+    }
+}
+
+impl WriteIdentity for Py2StrHashU32 {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        let value = value as u32;
+        if self.len == 0 {
+            self.hash ^= value << 7;
+        }
+        self.hash = self.hash.wrapping_mul(1000003) ^ value;
+        self.len = self.len.wrapping_add(1);
+    }
+}
+
+impl Hasher for Py2StrHashU32 {
+    fn finish(&self) -> u64 {
+        self.finish_u32() as u64
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does (hash * 1000003) XOR byte, after first XORing in the first byte shifted left 7 bits.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.len == 0 {
+                self.hash ^= (*byte as u32) << 7;
+            }
+            self.hash = self.hash.wrapping_mul(1000003) ^ *byte as u32;
+            self.len = self.len.wrapping_add(1);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for Py2StrHashU32 {
+    ///
+    /// The salt is the prefix of the secret and the length is what has been consumed, which like
+    /// Python is kept in 32 bits.
+    ///
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            extra: (self.suffix as u64).to_le_bytes().to_vec(),
+            ..Checkpoint::new(Algorithm::Py2StrHashU32, self.salt as u64, self.hash as u64, self.len as u64, self.int_encoding)
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        let extra = checkpoint.expect(Algorithm::Py2StrHashU32, 8)?;
+        let mut suffix = [0u8; 8];
+        suffix.copy_from_slice(extra);
+        let suffix = u64::from_le_bytes(suffix);
+        if suffix >> 32 != 0 {
+            return Err(CheckpointError::InvalidExtra);
+        }
+        if checkpoint.consumed >> 32 != 0 {
+            return Err(CheckpointError::OutOfRange);
+        }
+        Ok(Py2StrHashU32 {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            len: checkpoint.consumed as u32,
+            suffix: suffix as u32,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let vectors: [(&[u8], i32); 5] = [
+            (b"", 0),
+            (b"a", -468864544),
+            (b"Ez", -1722297609),
+            (b"python", 1142331976),
+            (b"abcdefghijklmnopqrstuvwxyz", 677538467),
+        ];
+        for &(input, expected) in vectors.iter() {
+            let mut sut = Py2StrHashU32::new();
+            sut.write(input);
+            assert_eq!(sut.finish_signed(), expected);
+            assert_eq!(sut.finish_u32(), expected as u32);
+            assert_eq!(sut.finish(), expected as u32 as u64);
+        }
+    }
+}
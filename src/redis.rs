@@ -0,0 +1,229 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Emulation of the legacy Redis dict hash functions.
+//!
+//! Old versions of Redis hashed their dict keys with the 32 bit times 33 hash
+//! starting from `dict_hash_function_seed`, which defaults to 5381, making
+//! them X33aU32 with its usual salt:
+//!
+//! * `dictGenHashFunction()` was the DJB hash up to Redis 2.4. Redis 2.6
+//!   moved it to MurmurHash2, which is not emulated here.
+//! * `dictGenCaseHashFunction()` lower cased each byte with the C locale
+//!   `tolower()` first and stayed the DJB hash until Redis 4.0 moved every
+//!   dict to SipHash.
+//!
+//! The vectors in the tests were worked out from `dict.c` of those versions.
+//! Redis dict tables are always a power of two in size and use `hash &
+//! (size - 1)` to pick the bucket.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::redis;
+//! assert_eq!(redis::dict_gen_hash_function(b"foo"), 193491849);
+//! assert_eq!(redis::dict_gen_case_hash_function(b"Hello World"), 894552257);
+//! ```
+//!
+use checkpoint::{Checkpoint, CheckpointError, Resumable};
+use int_encoding::WriteIdentity;
+use std::hash::Hasher;
+use x33a_u32::X33aU32;
+use Algorithm;
+use HasherU32;
+use IntEncoding;
+
+///
+/// The default value of Redis's `dict_hash_function_seed`.
+///
+pub const DICT_HASH_FUNCTION_SEED: u32 = 5381;
+
+///
+/// Returns a hasher that works like `dictGenHashFunction()`.
+///
+/// Use X33aU32::new_with_salt() directly if the server was started with a
+/// different seed.
+///
+pub fn hasher() -> X33aU32 {
+    X33aU32::new_with_salt(DICT_HASH_FUNCTION_SEED)
+}
+
+///
+/// Returns the hash `dictGenHashFunction()` gives in Redis 2.4 and earlier.
+///
+pub fn dict_gen_hash_function(buf: &[u8]) -> u32 {
+    let mut hasher = hasher();
+    hasher.write(buf);
+    hasher.finish_u32()
+}
+
+///
+/// Returns the hash `dictGenCaseHashFunction()` gives before Redis 4.0.
+///
+pub fn dict_gen_case_hash_function(buf: &[u8]) -> u32 {
+    let mut hasher = RedisCaseHash::new();
+    hasher.write(buf);
+    hasher.finish_u32()
+}
+
+///
+/// Implements `dictGenCaseHashFunction()` as a hasher.
+///
+/// Only the ASCII letters are lower cased, the same as `tolower()` does in the
+/// C locale Redis runs in.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::redis::*;
+/// let mut hasher1 = RedisCaseHash::new();
+/// let mut hasher2 = RedisCaseHash::new();
+/// hasher1.write(b"Hello World");
+/// hasher2.write(b"hello world");
+/// assert_eq!(hasher1.finish_u32(), hasher2.finish_u32());
+/// ```
+///
+pub struct RedisCaseHash {
+    hasher: X33aU32,
+    int_encoding: IntEncoding,
+}
+
+impl RedisCaseHash {
+    ///
+    /// Creates a new hash using the default seed of 5381.
+    ///
+    pub fn new() -> Self {
+        RedisCaseHash::new_with_salt(DICT_HASH_FUNCTION_SEED)
+    }
+    ///
+    /// Creates a new hash using a server configured seed.
+    ///
+    pub fn new_with_salt(s: u32) -> Self {
+        RedisCaseHash {
+            hasher: X33aU32::new_with_salt(s),
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for RedisCaseHash {
+    ///
+    /// Same as new(), using the default seed of 5381.
+    ///
+    fn default() -> Self {
+        RedisCaseHash::new()
+    }
+}
+
+impl HasherU32 for RedisCaseHash {
+    fn finish_u32(&self) -> u32 {
+        self.hasher.finish_u32()
+    }
+}
+
+impl WriteIdentity for RedisCaseHash {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.hasher.write_identity(value);
+    }
+}
+
+impl Hasher for RedisCaseHash {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        let mut buffer = [0u8; 64];
+        for chunk in bytes.chunks(buffer.len()) {
+            for (lower, byte) in buffer.iter_mut().zip(chunk) {
+                *lower = byte.to_ascii_lowercase();
+            }
+            self.hasher.write(&buffer[..chunk.len()]);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for RedisCaseHash {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            algorithm: Algorithm::RedisCaseHash,
+            int_encoding: self.int_encoding,
+            ..self.hasher.checkpoint()
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        checkpoint.expect(Algorithm::RedisCaseHash, 0)?;
+        let inner = Checkpoint {
+            algorithm: Algorithm::X33aU32,
+            int_encoding: IntEncoding::LittleEndian,
+            ..checkpoint.clone()
+        };
+        Ok(RedisCaseHash {
+            hasher: X33aU32::restore(&inner)?,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let vectors: [(&[u8], u32, u32); 5] = [
+            (b"", 5381, 5381),
+            (b"foo", 193_491_849, 193_491_849),
+            (b"key:1000", 3_093_737_129, 3_093_737_129),
+            (b"Hello World", 2_272_792_705, 894_552_257),
+            (b"caf\xc3\xa9", 255_161_979, 255_161_979),
+        ];
+        for &(buf, exact, case) in &vectors {
+            assert_eq!(dict_gen_hash_function(buf), exact);
+            assert_eq!(dict_gen_case_hash_function(buf), case);
+        }
+        let key = "Session:ABCDEFGHIJKLMNOPQRSTUVWXYZ:".repeat(5);
+        assert_eq!(
+            dict_gen_case_hash_function(key.as_bytes()),
+            dict_gen_hash_function(key.to_ascii_lowercase().as_bytes())
+        );
+    }
+}
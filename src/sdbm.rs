@@ -0,0 +1,178 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{Checkpoint, CheckpointError, Resumable};
+use super::HasherU32;
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements the hash from Ozan Yigit's sdbm, also used by Berkeley DB and many ndbm clones.
+///
+/// It is a 32 bit times 65599 hash starting from 0. 65599 is a prime close to 2^16 which spreads
+/// each byte over the upper half of the hash quickly. The original unrolls the loop with Duff's
+/// device but that does not change the result.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::sdbm::*;
+/// let input = "hello";
+/// let mut hasher = Sdbm::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish(), 684824882u64);
+/// assert_eq!(hasher.finish_u32(), 684824882u32);
+/// ```
+///
+/// Unlike X33a the strings "Ez" and "FY" do not collide:
+///
+/// ```rust
+/// # use std::hash::Hasher;
+/// # use djb_hash::sdbm::*;
+/// let mut hasher1 = Sdbm::new();
+/// let mut hasher2 = Sdbm::new();
+/// hasher1.write(&"Ez".as_bytes());
+/// hasher2.write(&"FY".as_bytes());
+/// assert_eq!(hasher1.finish(), 4526453u64);
+/// assert_eq!(hasher2.finish(), 4592019u64);
+/// ```
+///
+pub struct Sdbm {
+    hash: u32,
+    salt: u32,
+    consumed: u64,
+    int_encoding: IntEncoding,
+}
+
+impl Sdbm {
+    ///
+    /// Creates a new hash starting from zero like sdbm does.
+    ///
+    pub fn new() -> Self {
+        Sdbm { hash: 0, salt: 0, consumed: 0, int_encoding: IntEncoding::LittleEndian }
+    }
+    ///
+    /// Creates a new hash using user supplied salt value.
+    ///
+    pub fn new_with_salt(s: u32) -> Self {
+        Sdbm { hash: s, salt: s, consumed: 0, int_encoding: IntEncoding::LittleEndian }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for Sdbm {
+    ///
+    /// Same as new(), starting from zero.
+    ///
+    fn default() -> Self {
+        Sdbm::new()
+    }
+}
+
+impl HasherU32 for Sdbm {
+    fn finish_u32(&self) -> u32 {
+        self.hash
+    }
+}
+
+impl WriteIdentity for Sdbm {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (value as u32)
+            .wrapping_add(self.hash << 6)
+            .wrapping_add(self.hash << 16)
+            .wrapping_sub(self.hash);
+    }
+}
+
+impl Hasher for Sdbm {
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does hash * 65599 + byte, which sdbm writes as byte + (hash << 6) + (hash << 16) - hash.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        for byte in bytes {
+            self.hash = (*byte as u32)
+                .wrapping_add(self.hash << 6)
+                .wrapping_add(self.hash << 16)
+                .wrapping_sub(self.hash);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for Sdbm {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(Algorithm::Sdbm, self.salt as u64, self.hash as u64, self.consumed, self.int_encoding)
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        checkpoint.expect(Algorithm::Sdbm, 0)?;
+        Ok(Sdbm {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            consumed: checkpoint.consumed,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let mut sut = Sdbm::new();
+        sut.write(b"");
+        assert_eq!(sut.finish(), 0u64);
+        let mut sut = Sdbm::new();
+        sut.write(b"key001");
+        assert_eq!(sut.finish_u32(), 643415858u32);
+        let mut sut = Sdbm::new_with_salt(0);
+        sut.write(b"%$sniglet^&\0");
+        assert_eq!(sut.finish_u32(), 696955637u32);
+    }
+}
@@ -0,0 +1,376 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Hashing that gives the same value on every Rust version and platform.
+//!
+//! `#[derive(Hash)]` is fine for a `HashMap` but makes no promise about the
+//! bytes it feeds a hasher: `usize` follows the target, integers go through
+//! the hasher's own write_*() functions and the framing of `str` and slices is
+//! an implementation detail. `DjbHash` writes a documented byte stream
+//! instead, so a hash of it can be stored or compared across machines. It can
+//! be derived for structs and enums with `#[derive(DjbHash)]` from the
+//! djb_hash_macros crate.
+//!
+//! # Byte stream
+//!
+//! Everything goes to the hasher with write() only.
+//!
+//! * Integers are their little-endian bytes, with `usize` and `isize` always
+//!   8 bytes like `u64` and `i64`. `bool` is one byte of 0 or 1, `char` is its
+//!   code point as a `u32` and floats are the bits of `to_bits()` as an
+//!   integer.
+//! * `str` and `String` are their length in bytes as a `u64` then the UTF-8
+//!   bytes. `[u8]` and `Vec<u8>` are framed the same way.
+//! * Slices, arrays, `Vec` and `VecDeque` are the number of elements as a
+//!   `u64` then each element. `BTreeSet` and `BTreeMap` are the same in their
+//!   sorted order, with each map entry as the key then the value.
+//! * `Option` is a byte of 0 for `None` or 1 followed by the value for
+//!   `Some`. `Result` is a byte of 0 and the value for `Ok` or 1 and the
+//!   error for `Err`.
+//! * Tuples, and derived structs, are each field in order with nothing
+//!   between them. `()` and unit structs write nothing.
+//! * Derived enums are the index of the variant in declaration order, counting
+//!   from zero, as a `u32` then the fields of the variant. Explicit
+//!   discriminants are not used.
+//! * References, `Box`, `Rc` and `Arc` are the value they point to.
+//!
+//! Since every variable length part has its length in front,
+//! ("ab", "c") and ("a", "bc") are different byte streams.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use djb_hash::DjbHash;
+//! use djb_hash::x33a_u32::X33aU32;
+//! let mut hasher1 = X33aU32::new();
+//! let mut hasher2 = X33aU32::new();
+//! ("ab", "c").djb_hash(&mut hasher1);
+//! ("a", "bc").djb_hash(&mut hasher2);
+//! assert_ne!(hasher1.finish(), hasher2.finish());
+//! let mut hasher3 = X33aU32::new();
+//! hasher3.write(&[2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b', 1, 0, 0, 0, 0, 0, 0, 0, b'c']);
+//! assert_eq!(hasher1.finish(), hasher3.finish());
+//! ```
+//!
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::hash::Hasher;
+use std::rc::Rc;
+use std::sync::Arc;
+
+///
+/// A value that can be hashed as a fixed, platform independent byte stream.
+///
+/// See the module documentation for the bytes each type writes.
+///
+pub trait DjbHash {
+    ///
+    /// Writes the value to the hasher.
+    ///
+    fn djb_hash<H: Hasher>(&self, state: &mut H);
+    ///
+    /// Writes each value in the slice to the hasher, without a length.
+    ///
+    /// Used by the slice impls so `u8` can be written in a single call.
+    ///
+    fn djb_hash_slice<H: Hasher>(data: &[Self], state: &mut H)
+    where
+        Self: Sized,
+    {
+        for value in data {
+            value.djb_hash(state);
+        }
+    }
+}
+
+///
+/// Writes the length in front of a variable length value.
+///
+fn write_len<H: Hasher>(len: usize, state: &mut H) {
+    state.write(&(len as u64).to_le_bytes());
+}
+
+macro_rules! djb_hash_int {
+    ($($t:ty),*) => {
+        $(
+            impl DjbHash for $t {
+                fn djb_hash<H: Hasher>(&self, state: &mut H) {
+                    state.write(&self.to_le_bytes());
+                }
+            }
+        )*
+    };
+}
+
+djb_hash_int!(u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl DjbHash for u8 {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        state.write(&[*self]);
+    }
+    fn djb_hash_slice<H: Hasher>(data: &[Self], state: &mut H) {
+        state.write(data);
+    }
+}
+
+impl DjbHash for usize {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        (*self as u64).djb_hash(state);
+    }
+}
+
+impl DjbHash for isize {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        (*self as i64).djb_hash(state);
+    }
+}
+
+impl DjbHash for bool {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        state.write(&[*self as u8]);
+    }
+}
+
+impl DjbHash for char {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        (*self as u32).djb_hash(state);
+    }
+}
+
+impl DjbHash for f32 {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bits().djb_hash(state);
+    }
+}
+
+impl DjbHash for f64 {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        self.to_bits().djb_hash(state);
+    }
+}
+
+impl DjbHash for str {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        write_len(self.len(), state);
+        state.write(self.as_bytes());
+    }
+}
+
+impl DjbHash for String {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().djb_hash(state);
+    }
+}
+
+impl<T: DjbHash> DjbHash for [T] {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        write_len(self.len(), state);
+        T::djb_hash_slice(self, state);
+    }
+}
+
+impl<T: DjbHash, const N: usize> DjbHash for [T; N] {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        self[..].djb_hash(state);
+    }
+}
+
+impl<T: DjbHash> DjbHash for Vec<T> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        self[..].djb_hash(state);
+    }
+}
+
+impl<T: DjbHash> DjbHash for VecDeque<T> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        write_len(self.len(), state);
+        for value in self {
+            value.djb_hash(state);
+        }
+    }
+}
+
+impl<T: DjbHash> DjbHash for BTreeSet<T> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        write_len(self.len(), state);
+        for value in self {
+            value.djb_hash(state);
+        }
+    }
+}
+
+impl<K: DjbHash, V: DjbHash> DjbHash for BTreeMap<K, V> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        write_len(self.len(), state);
+        for (key, value) in self {
+            key.djb_hash(state);
+            value.djb_hash(state);
+        }
+    }
+}
+
+impl<T: DjbHash> DjbHash for Option<T> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            None => state.write(&[0]),
+            Some(ref value) => {
+                state.write(&[1]);
+                value.djb_hash(state);
+            }
+        }
+    }
+}
+
+impl<T: DjbHash, E: DjbHash> DjbHash for Result<T, E> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        match *self {
+            Ok(ref value) => {
+                state.write(&[0]);
+                value.djb_hash(state);
+            }
+            Err(ref error) => {
+                state.write(&[1]);
+                error.djb_hash(state);
+            }
+        }
+    }
+}
+
+impl<T: DjbHash + ?Sized> DjbHash for &T {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        (**self).djb_hash(state);
+    }
+}
+
+impl<T: DjbHash + ?Sized> DjbHash for &mut T {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        (**self).djb_hash(state);
+    }
+}
+
+impl<T: DjbHash + ?Sized> DjbHash for Box<T> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        (**self).djb_hash(state);
+    }
+}
+
+impl<T: DjbHash + ?Sized> DjbHash for Rc<T> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        (**self).djb_hash(state);
+    }
+}
+
+impl<T: DjbHash + ?Sized> DjbHash for Arc<T> {
+    fn djb_hash<H: Hasher>(&self, state: &mut H) {
+        (**self).djb_hash(state);
+    }
+}
+
+impl DjbHash for () {
+    fn djb_hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+macro_rules! djb_hash_tuple {
+    ($(($($name:ident)+))+) => {
+        $(
+            impl<$($name: DjbHash),+> DjbHash for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn djb_hash<H: Hasher>(&self, state: &mut H) {
+                    let ($(ref $name,)+) = *self;
+                    $($name.djb_hash(state);)+
+                }
+            }
+        )+
+    };
+}
+
+djb_hash_tuple! {
+    (A)
+    (A B)
+    (A B C)
+    (A B C D)
+    (A B C D E)
+    (A B C D E F)
+    (A B C D E F G)
+    (A B C D E F G I)
+    (A B C D E F G I J)
+    (A B C D E F G I J K)
+    (A B C D E F G I J K L)
+    (A B C D E F G I J K L M)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x33a_u32::X33aU32;
+
+    struct Bytes(Vec<u8>);
+
+    impl Hasher for Bytes {
+        fn finish(&self) -> u64 {
+            0
+        }
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
+
+    fn bytes<T: DjbHash + ?Sized>(value: &T) -> Vec<u8> {
+        let mut sut = Bytes(Vec::new());
+        value.djb_hash(&mut sut);
+        sut.0
+    }
+
+    #[test]
+    fn it_does_hash_correctly() {
+        assert_eq!(bytes(&0x0102u16), [2, 1]);
+        assert_eq!(bytes(&-2i32), [0xfe, 0xff, 0xff, 0xff]);
+        assert_eq!(bytes(&7usize), [7, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(bytes(&-1isize), [0xff; 8]);
+        assert_eq!(bytes(&true), [1]);
+        assert_eq!(bytes(&'é'), [0xe9, 0, 0, 0]);
+        assert_eq!(bytes(&1.0f32), [0, 0, 0x80, 0x3f]);
+        assert_eq!(bytes("hé"), [3, 0, 0, 0, 0, 0, 0, 0, b'h', 0xc3, 0xa9]);
+        assert_eq!(bytes(&b"ab"[..]), [2, 0, 0, 0, 0, 0, 0, 0, b'a', b'b']);
+        assert_eq!(bytes(&[1u16, 2]), [2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2, 0]);
+        assert_eq!(
+            bytes(&vec![Some(1u8), None]),
+            [2, 0, 0, 0, 0, 0, 0, 0, 1, 1, 0]
+        );
+        assert_eq!(bytes(&Err::<u8, bool>(true)), [1, 1]);
+        assert_eq!(bytes(&(1u8, (), Box::new(2u8))), [1, 2]);
+        let map: BTreeMap<u8, u8> = [(2, 20), (1, 10)].iter().cloned().collect();
+        assert_eq!(bytes(&map), [2, 0, 0, 0, 0, 0, 0, 0, 1, 10, 2, 20]);
+        assert_ne!(bytes(&("ab", "c")), bytes(&("a", "bc")));
+        assert_eq!(bytes(&String::from("ab")), bytes("ab"));
+        let mut sut = X33aU32::new();
+        ("ab", "c").djb_hash(&mut sut);
+        assert_eq!(sut.finish(), 502_224_654);
+    }
+}
@@ -0,0 +1,184 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{flag, Checkpoint, CheckpointError, Resumable};
+use super::HasherU32;
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements the string key hash of Tcl hash tables, `HashStringKey()` in `tclHash.c`.
+///
+/// It is a 32 bit times 9 hash, result += (result << 3) + byte, starting from 0 that stops at the
+/// first NUL byte like the C string keys it was made for. Once a NUL byte is written any later
+/// bytes are ignored. The vectors in the tests come from Tcl 8.6.15. Older releases read plain
+/// `char`s, which are signed on x86, and so may give other hashes for bytes 0x80 and up.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::tcl_hash::*;
+/// let input = "hello world";
+/// let mut hasher = TclHash::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish(), 3255479292u64);
+/// assert_eq!(hasher.finish_u32(), 3255479292u32);
+/// ```
+///
+pub struct TclHash {
+    hash: u32,
+    salt: u32,
+    consumed: u64,
+    ended: bool,
+    int_encoding: IntEncoding,
+}
+
+impl TclHash {
+    ///
+    /// Creates a new hash starting from zero like Tcl does.
+    ///
+    pub fn new() -> Self {
+        TclHash {
+            hash: 0,
+            salt: 0,
+            consumed: 0,
+            ended: false,
+            int_encoding: IntEncoding::LittleEndian,
+        }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for TclHash {
+    ///
+    /// Same as new(), starting from zero.
+    ///
+    fn default() -> Self {
+        TclHash::new()
+    }
+}
+
+impl HasherU32 for TclHash {
+    fn finish_u32(&self) -> u32 {
+        self.hash
+    }
+}
+
+impl WriteIdentity for TclHash {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        if !self.ended {
+            self.hash = self.hash.wrapping_add(self.hash << 3).wrapping_add(value as u32);
+        }
+    }
+}
+
+impl Hasher for TclHash {
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does hash * 9 + byte, implemented as hash + (hash << 3) + byte, until the first NUL byte.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        if self.ended {
+            return;
+        }
+        for byte in bytes {
+            if *byte == 0 {
+                self.ended = true;
+                return;
+            }
+            self.hash = self.hash.wrapping_add(self.hash << 3).wrapping_add(*byte as u32);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for TclHash {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            extra: vec![self.ended as u8],
+            ..Checkpoint::new(Algorithm::TclHash, self.salt as u64, self.hash as u64, self.consumed, self.int_encoding)
+        }
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        let extra = checkpoint.expect(Algorithm::TclHash, 1)?;
+        Ok(TclHash {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            consumed: checkpoint.consumed,
+            ended: flag(extra[0])?,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        // Values from the hash field of Tcl_HashEntry in Tcl 8.6.15.
+        let vectors: [(&[u8], u32); 7] = [
+            (b"", 0),
+            (b"a", 97),
+            (b"Ez", 743),
+            (b"FY", 719),
+            (b"abcdefghijklmnopqrstuvwxyz", 2258889991),
+            (b"caf\xc3\xa9", 730438),
+            (b"\xff\xfe", 2549),
+        ];
+        for &(input, expected) in vectors.iter() {
+            let mut sut = TclHash::new();
+            sut.write(input);
+            assert_eq!(sut.finish_u32(), expected);
+        }
+        let mut sut = TclHash::new();
+        sut.write(b"abc\0");
+        sut.write(b"def");
+        assert_eq!(sut.finish_u32(), 8838);
+    }
+}
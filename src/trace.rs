@@ -0,0 +1,595 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Tracing a hasher to find where two hashes part ways.
+//!
+//! When a hash from this crate and one from PHP, Java or C disagree it helps
+//! to see the running state after every byte. `Traced` wraps any hasher in
+//! the crate and records each write() and write_*() call, splitting write()
+//! into single bytes, along with the state after each step as checkpoint()
+//! gives it. The `Trace` can be shown as a table or as JSON, and diff() of
+//! two traces points at the first step where the input or the state differ.
+//!
+//! Each step takes a checkpoint of the hasher, so tracing is slow and for
+//! `LuaHash`, which keeps every byte until finish(), the state stays 0.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::hash::Hasher;
+//! use djb_hash::trace::*;
+//! use djb_hash::x33a_u32::X33aU32;
+//! use djb_hash::x33a_u32_php::X33aU32Php;
+//! let mut rust = Traced::new(X33aU32Php::new());
+//! rust.write(b"Ez");
+//! let mut other = Traced::new(X33aU32Php::new());
+//! other.write(b"EZ");
+//! let divergence = rust.trace().diff(other.trace()).unwrap();
+//! assert_eq!(divergence.step, 1);
+//! assert_eq!(divergence.bytes, 1);
+//! println!("{}", rust.trace().to_table());
+//! ```
+//!
+use checkpoint::Resumable;
+use std::fmt;
+use std::hash::Hasher;
+use Algorithm;
+use HasherU32;
+use HasherUnit;
+
+///
+/// What went into the hasher in one step.
+///
+/// `Byte` is a single byte of a write(), the others are a whole call of the
+/// write_*() function of the same name or write_unit().
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Input {
+    Byte(u8),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    Usize(u64),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    Isize(i64),
+    Unit(u32),
+}
+
+impl Input {
+    ///
+    /// Returns the name of the hasher function that took the input.
+    ///
+    pub fn method(&self) -> &'static str {
+        match *self {
+            Input::Byte(_) => "write",
+            Input::U8(_) => "write_u8",
+            Input::U16(_) => "write_u16",
+            Input::U32(_) => "write_u32",
+            Input::U64(_) => "write_u64",
+            Input::U128(_) => "write_u128",
+            Input::Usize(_) => "write_usize",
+            Input::I8(_) => "write_i8",
+            Input::I16(_) => "write_i16",
+            Input::I32(_) => "write_i32",
+            Input::I64(_) => "write_i64",
+            Input::I128(_) => "write_i128",
+            Input::Isize(_) => "write_isize",
+            Input::Unit(_) => "write_unit",
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    ///
+    /// Writes the value, bytes in hex with the character when printable.
+    ///
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Input::Byte(b) if b.is_ascii_graphic() || b == b' ' => {
+                write!(f, "0x{:02x} '{}'", b, b as char)
+            }
+            Input::Byte(b) => write!(f, "0x{:02x}", b),
+            Input::U8(v) => write!(f, "{}", v),
+            Input::U16(v) => write!(f, "{}", v),
+            Input::U32(v) | Input::Unit(v) => write!(f, "{}", v),
+            Input::U64(v) | Input::Usize(v) => write!(f, "{}", v),
+            Input::U128(v) => write!(f, "{}", v),
+            Input::I8(v) => write!(f, "{}", v),
+            Input::I16(v) => write!(f, "{}", v),
+            Input::I32(v) => write!(f, "{}", v),
+            Input::I64(v) | Input::Isize(v) => write!(f, "{}", v),
+            Input::I128(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+///
+/// One step of a trace.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Step {
+    ///
+    /// The index of the write call the step is part of.
+    ///
+    pub call: usize,
+    ///
+    /// What went into the hasher.
+    ///
+    pub input: Input,
+    ///
+    /// The running hash total after the step.
+    ///
+    pub state: u64,
+}
+
+///
+/// Everything written to a `Traced` hasher.
+///
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Trace {
+    algorithm: Algorithm,
+    start: u64,
+    steps: Vec<Step>,
+}
+
+impl Trace {
+    ///
+    /// Returns the algorithm of the traced hasher.
+    ///
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+    ///
+    /// Returns the state before the first step.
+    ///
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+    ///
+    /// Returns the steps in the order they were written.
+    ///
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+    ///
+    /// Returns the trace as a plain text table, with the state in hex padded
+    /// to the width of the algorithm.
+    ///
+    pub fn to_table(&self) -> String {
+        let width = (self.algorithm.bits() / 4) as usize;
+        let mut table = format!(
+            "{:>5} {:>5} {:<12} {:<24} state\n{:>5} {:>5} {:<12} {:<24} {:0w$x}\n",
+            "step",
+            "call",
+            "method",
+            "input",
+            "",
+            "",
+            "",
+            "",
+            self.start,
+            w = width
+        );
+        for (index, step) in self.steps.iter().enumerate() {
+            table.push_str(&format!(
+                "{:>5} {:>5} {:<12} {:<24} {:0w$x}\n",
+                index,
+                step.call,
+                step.input.method(),
+                step.input.to_string(),
+                step.state,
+                w = width
+            ));
+        }
+        table
+    }
+    ///
+    /// Returns the trace as JSON, with each input as a number.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::hash::Hasher;
+    /// use djb_hash::trace::Traced;
+    /// use djb_hash::x33a_u32::X33aU32;
+    /// let mut hasher = Traced::new(X33aU32::new());
+    /// hasher.write(b"E");
+    /// hasher.write_u8(122);
+    /// assert_eq!(
+    ///     hasher.trace().to_json(),
+    ///     "{\"algorithm\":\"x33a_u32\",\"start\":5381,\"steps\":[\
+    ///     {\"call\":0,\"method\":\"write\",\"input\":69,\"state\":177642},\
+    ///     {\"call\":1,\"method\":\"write_u8\",\"input\":122,\"state\":5862308}]}"
+    /// );
+    /// ```
+    ///
+    pub fn to_json(&self) -> String {
+        let mut json = format!(
+            "{{\"algorithm\":\"{}\",\"start\":{},\"steps\":[",
+            self.algorithm, self.start
+        );
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            let input = match step.input {
+                Input::Byte(b) => b.to_string(),
+                input => input.to_string(),
+            };
+            json.push_str(&format!(
+                "{{\"call\":{},\"method\":\"{}\",\"input\":{},\"state\":{}}}",
+                step.call,
+                step.input.method(),
+                input,
+                step.state
+            ));
+        }
+        json.push_str("]}");
+        json
+    }
+    ///
+    /// Returns the first step where the two traces have a different input or
+    /// state, or None when they are the same.
+    ///
+    /// How the input was split into calls is not compared, so writing `"Ez"`
+    /// at once and as `"E"` and `"z"` gives the same steps. A difference in
+    /// the algorithm or the starting state is given as step 0, since hashers
+    /// like `X33aU32` and `X33aU32Php` step through the same states but give
+    /// different finish() values. When one trace is a prefix of the other the
+    /// first extra step is the divergence.
+    ///
+    pub fn diff(&self, other: &Trace) -> Option<Divergence> {
+        let header = self.algorithm != other.algorithm || self.start != other.start;
+        let mut bytes = 0;
+        let len = self.steps.len().max(other.steps.len());
+        for step in 0..len {
+            let left = self.steps.get(step).cloned();
+            let right = other.steps.get(step).cloned();
+            let same = match (left, right) {
+                (Some(l), Some(r)) => l.input == r.input && l.state == r.state,
+                _ => false,
+            };
+            if !same || (step == 0 && header) {
+                return Some(Divergence {
+                    step,
+                    bytes,
+                    left,
+                    right,
+                });
+            }
+            if let Some(Step {
+                input: Input::Byte(_),
+                ..
+            }) = left
+            {
+                bytes += 1;
+            }
+        }
+        if header {
+            return Some(Divergence {
+                step: 0,
+                bytes: 0,
+                left: None,
+                right: None,
+            });
+        }
+        None
+    }
+}
+
+///
+/// Where two traces part ways.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Divergence {
+    ///
+    /// The index of the first step that differs.
+    ///
+    pub step: usize,
+    ///
+    /// The number of bytes both traces wrote with write() before that step,
+    /// which is the offset of the differing byte when it is one.
+    ///
+    pub bytes: u64,
+    ///
+    /// The step in the first trace, if it has one.
+    ///
+    pub left: Option<Step>,
+    ///
+    /// The step in the second trace, if it has one.
+    ///
+    pub right: Option<Step>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "traces differ at step {} after {} bytes:",
+            self.step, self.bytes
+        )?;
+        for &(side, step) in [("left", self.left), ("right", self.right)].iter() {
+            match step {
+                Some(step) => write!(
+                    f,
+                    " {} {}({}) gives 0x{:x}",
+                    side,
+                    step.input.method(),
+                    step.input,
+                    step.state
+                )?,
+                None => write!(f, " {} has no step", side)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+///
+/// Records every write to the wrapped hasher and the state after it.
+///
+/// Works with any hasher in the crate and can be used in place of it as it
+/// gives the same finish(), finish_u32() and write_unit().
+///
+pub struct Traced<H> {
+    hasher: H,
+    trace: Trace,
+    calls: usize,
+}
+
+impl<H: Resumable> Traced<H> {
+    ///
+    /// Creates a new traced hasher.
+    ///
+    pub fn new(hasher: H) -> Self {
+        let checkpoint = hasher.checkpoint();
+        Traced {
+            hasher,
+            trace: Trace {
+                algorithm: checkpoint.algorithm,
+                start: checkpoint.state,
+                steps: Vec::new(),
+            },
+            calls: 0,
+        }
+    }
+    ///
+    /// Returns the trace so far.
+    ///
+    pub fn trace(&self) -> &Trace {
+        &self.trace
+    }
+    ///
+    /// Returns a reference to the wrapped hasher.
+    ///
+    pub fn get_ref(&self) -> &H {
+        &self.hasher
+    }
+    ///
+    /// Returns the wrapped hasher and the trace.
+    ///
+    pub fn into_parts(self) -> (H, Trace) {
+        (self.hasher, self.trace)
+    }
+    fn step(&mut self, input: Input) {
+        self.trace.steps.push(Step {
+            call: self.calls,
+            input,
+            state: self.hasher.checkpoint().state,
+        });
+    }
+    fn call(&mut self, input: Input) {
+        self.step(input);
+        self.calls += 1;
+    }
+}
+
+impl<H: Resumable + HasherU32> HasherU32 for Traced<H> {
+    fn finish_u32(&self) -> u32 {
+        self.hasher.finish_u32()
+    }
+}
+
+impl<H: Resumable + HasherUnit> HasherUnit for Traced<H> {
+    fn write_unit(&mut self, unit: u32) {
+        self.hasher.write_unit(unit);
+        self.call(Input::Unit(unit));
+    }
+}
+
+impl<H: Resumable> Hasher for Traced<H> {
+    fn finish(&self) -> u64 {
+        self.hasher.finish()
+    }
+    ///
+    /// Writes the bytes one at a time, each as a step of the trace.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hasher.write(&[byte]);
+            self.step(Input::Byte(byte));
+        }
+        self.calls += 1;
+    }
+    fn write_u8(&mut self, i: u8) {
+        self.hasher.write_u8(i);
+        self.call(Input::U8(i));
+    }
+    fn write_u16(&mut self, i: u16) {
+        self.hasher.write_u16(i);
+        self.call(Input::U16(i));
+    }
+    fn write_u32(&mut self, i: u32) {
+        self.hasher.write_u32(i);
+        self.call(Input::U32(i));
+    }
+    fn write_u64(&mut self, i: u64) {
+        self.hasher.write_u64(i);
+        self.call(Input::U64(i));
+    }
+    fn write_u128(&mut self, i: u128) {
+        self.hasher.write_u128(i);
+        self.call(Input::U128(i));
+    }
+    fn write_usize(&mut self, i: usize) {
+        self.hasher.write_usize(i);
+        self.call(Input::Usize(i as u64));
+    }
+    fn write_i8(&mut self, i: i8) {
+        self.hasher.write_i8(i);
+        self.call(Input::I8(i));
+    }
+    fn write_i16(&mut self, i: i16) {
+        self.hasher.write_i16(i);
+        self.call(Input::I16(i));
+    }
+    fn write_i32(&mut self, i: i32) {
+        self.hasher.write_i32(i);
+        self.call(Input::I32(i));
+    }
+    fn write_i64(&mut self, i: i64) {
+        self.hasher.write_i64(i);
+        self.call(Input::I64(i));
+    }
+    fn write_i128(&mut self, i: i128) {
+        self.hasher.write_i128(i);
+        self.call(Input::I128(i));
+    }
+    fn write_isize(&mut self, i: isize) {
+        self.hasher.write_isize(i);
+        self.call(Input::Isize(i as i64));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nginx::NgxHashKey;
+    use x33a_u32::X33aU32;
+    use x33a_u32_php::X33aU32Php;
+    use IntEncoding;
+
+    #[test]
+    fn it_does_trace_correctly() {
+        let mut plain = X33aU32Php::new().int_encoding(IntEncoding::Language);
+        let mut sut = Traced::new(X33aU32Php::new().int_encoding(IntEncoding::Language));
+        for sink in [&mut plain as &mut dyn Hasher, &mut sut].iter_mut() {
+            sink.write(b"Ez");
+            sink.write_i32(-1);
+        }
+        sut.write_unit(0x20ac);
+        plain.write_unit(0x20ac);
+        assert_eq!(sut.finish(), plain.finish());
+        assert_eq!(sut.finish_u32(), plain.finish_u32());
+        let trace = sut.trace();
+        assert_eq!(trace.algorithm(), Algorithm::X33aU32Php);
+        assert_eq!(trace.start(), 5381);
+        assert_eq!(
+            trace.steps(),
+            &[
+                Step {
+                    call: 0,
+                    input: Input::Byte(b'E'),
+                    state: 177_642,
+                },
+                Step {
+                    call: 0,
+                    input: Input::Byte(b'z'),
+                    state: 5_862_308,
+                },
+                Step {
+                    call: 1,
+                    input: Input::I32(-1),
+                    state: 193_456_163,
+                },
+                Step {
+                    call: 2,
+                    input: Input::Unit(0x20ac),
+                    state: 2_089_094_447,
+                },
+            ][..]
+        );
+        assert_eq!(
+            trace.to_table(),
+            [
+                " step  call method       input                    state",
+                "                                                  00001505",
+                "    0     0 write        0x45 'E'                 0002b5ea",
+                "    1     0 write        0x7a 'z'                 005973a4",
+                "    2     1 write_i32    -1                       0b87e823",
+                "    3     2 write_unit   8364                     7c850d2f",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn it_does_diff_correctly() {
+        let trace = |salt: u32, parts: &[&[u8]]| {
+            let mut sut = Traced::new(X33aU32Php::new_with_salt(salt));
+            for part in parts {
+                sut.write(part);
+            }
+            sut.into_parts().1
+        };
+        let whole = trace(5381, &[b"Hello"]);
+        assert_eq!(whole.diff(&trace(5381, &[b"He", b"llo"])), None);
+        let sut = whole.diff(&trace(5381, &[b"He", b"Llo"])).unwrap();
+        assert_eq!((sut.step, sut.bytes), (2, 2));
+        assert_eq!(sut.left.unwrap().input, Input::Byte(b'l'));
+        assert_eq!(sut.right.unwrap().input, Input::Byte(b'L'));
+        assert_eq!(
+            sut.to_string(),
+            "traces differ at step 2 after 2 bytes: left write(0x6c 'l') gives 0xb87f29e \
+             right write(0x4c 'L') gives 0xb87f27e"
+        );
+        let sut = whole.diff(&trace(5381, &[b"Hell"])).unwrap();
+        assert_eq!((sut.step, sut.bytes, sut.right), (4, 4, None));
+        let sut = whole.diff(&trace(0, &[b"Hello"])).unwrap();
+        assert_eq!(sut.step, 0);
+        assert_eq!(trace(1, &[]).diff(&trace(2, &[])).unwrap().step, 0);
+        let mut plain = Traced::new(X33aU32::new());
+        plain.write(b"Hello");
+        let sut = whole.diff(plain.trace()).unwrap();
+        assert_eq!((sut.step, sut.bytes), (0, 0));
+        assert_eq!(sut.left, sut.right);
+        let empty = Traced::new(X33aU32::new()).into_parts().1;
+        assert_eq!(trace(5381, &[]).diff(&empty).unwrap().step, 0);
+        let mut wide = Traced::new(NgxHashKey::new());
+        wide.write(b"a");
+        assert!(wide.trace().to_table().ends_with("0000000000000061\n"));
+    }
+}
@@ -0,0 +1,294 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Salt and variant tuning for fixed key sets.
+//!
+//! When the keys are known ahead of time, like config keys or protocol tags,
+//! it is worth trying a few salts and hash variants to find the one that
+//! spreads them best over a given table size. The `Tuner` tries every
+//! combination asked for and returns the best one ready to use along with how
+//! the runners-up did.
+//!
+//! Prefer prime table sizes. With a power of two size only the low bits of the
+//! hash are used and for keys of the same length those don't change with the
+//! salt, so no amount of salt searching will separate them.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use djb_hash::tune::*;
+//! let keys = ["GET", "PUT", "POST", "HEAD", "DELETE", "OPTIONS", "Ez", "FY"];
+//! let tuning = Tuner::new(13).tune(&keys);
+//! assert_eq!(tuning.report.collisions, 0);
+//! let bucket = tuning.bucket(b"POST");
+//! assert!(bucket < 13);
+//! ```
+//!
+use algorithm::Algorithm;
+use std::cmp::Ordering;
+use std::hash::Hasher;
+
+///
+/// Settings used to search for the best salt and variant.
+///
+#[derive(Clone, Debug)]
+pub struct Tuner {
+    table_size: usize,
+    algorithms: Vec<Algorithm>,
+    salts: Vec<u64>,
+    runners_up: usize,
+}
+
+impl Tuner {
+    ///
+    /// Creates a new tuner for a table with the given number of buckets.
+    ///
+    /// By default all of the DJB variants are tried with the original 5381
+    /// salt and the next 255 primes after it, and the best 5 runners-up are
+    /// kept.
+    ///
+    pub fn new(table_size: usize) -> Self {
+        assert!(table_size > 0, "Table size must be at least one bucket");
+        Tuner {
+            table_size,
+            algorithms: Algorithm::DJB.to_vec(),
+            salts: primes_from(5381).take(256).collect(),
+            runners_up: 5,
+        }
+    }
+    ///
+    /// Sets which hash variants are tried.
+    ///
+    pub fn algorithms(mut self, algorithms: &[Algorithm]) -> Self {
+        self.algorithms = algorithms.to_vec();
+        self
+    }
+    ///
+    /// Sets which salts are tried. The salts are tried in the order given and
+    /// the earlier one wins when two do equally well.
+    ///
+    pub fn salts<I: IntoIterator<Item = u64>>(mut self, salts: I) -> Self {
+        self.salts = salts.into_iter().collect();
+        self
+    }
+    ///
+    /// Sets how many of the runner-up choices are included in the result.
+    ///
+    pub fn runners_up(mut self, runners_up: usize) -> Self {
+        self.runners_up = runners_up;
+        self
+    }
+    ///
+    /// Tries every algorithm and salt combination and returns the best.
+    ///
+    /// Algorithms that have no salt, see `Algorithm::takes_salt()`, are only
+    /// tried once with their default salt.
+    ///
+    /// A combination with no collisions always wins. After that the one with
+    /// the lowest maximum bucket load wins with the fewest collisions used to
+    /// break ties.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no algorithms or no salts were given.
+    ///
+    pub fn tune<K: AsRef<[u8]>>(&self, keys: &[K]) -> Tuning {
+        assert!(!self.algorithms.is_empty(), "Need at least one algorithm");
+        assert!(!self.salts.is_empty(), "Need at least one salt");
+        let mut reports = Vec::with_capacity(self.algorithms.len() * self.salts.len());
+        let mut loads = vec![0usize; self.table_size];
+        for &algorithm in &self.algorithms {
+            if !algorithm.takes_salt() {
+                let salt = algorithm.default_salt();
+                reports.push(self.report(algorithm, salt, keys, &mut loads));
+                continue;
+            }
+            for &salt in &self.salts {
+                reports.push(self.report(algorithm, salt, keys, &mut loads));
+            }
+        }
+        // Stable sort so earlier algorithms and salts win ties.
+        reports.sort_by(CollisionReport::rank);
+        let report = reports.remove(0);
+        reports.truncate(self.runners_up);
+        Tuning {
+            algorithm: report.algorithm,
+            salt: report.salt,
+            table_size: self.table_size,
+            report,
+            runners_up: reports,
+        }
+    }
+    fn report<K: AsRef<[u8]>>(
+        &self,
+        algorithm: Algorithm,
+        salt: u64,
+        keys: &[K],
+        loads: &mut [usize],
+    ) -> CollisionReport {
+        for load in loads.iter_mut() {
+            *load = 0;
+        }
+        let mut collisions = 0;
+        for key in keys {
+            let load = &mut loads[bucket(algorithm, salt, self.table_size, key.as_ref())];
+            if *load > 0 {
+                collisions += 1;
+            }
+            *load += 1;
+        }
+        CollisionReport {
+            algorithm,
+            salt,
+            collisions,
+            max_load: loads.iter().cloned().max().unwrap_or(0),
+        }
+    }
+}
+
+///
+/// How well one algorithm and salt combination spread the keys.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CollisionReport {
+    pub algorithm: Algorithm,
+    pub salt: u64,
+    ///
+    /// Number of keys that landed in an already used bucket.
+    ///
+    pub collisions: usize,
+    ///
+    /// Most keys in any one bucket.
+    ///
+    pub max_load: usize,
+}
+
+impl CollisionReport {
+    fn rank(a: &CollisionReport, b: &CollisionReport) -> Ordering {
+        (a.collisions > 0)
+            .cmp(&(b.collisions > 0))
+            .then(a.max_load.cmp(&b.max_load))
+            .then(a.collisions.cmp(&b.collisions))
+    }
+}
+
+///
+/// Best algorithm and salt found by the tuner.
+///
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tuning {
+    pub algorithm: Algorithm,
+    pub salt: u64,
+    pub table_size: usize,
+    pub report: CollisionReport,
+    ///
+    /// The next best choices in order, best first.
+    ///
+    pub runners_up: Vec<CollisionReport>,
+}
+
+impl Tuning {
+    ///
+    /// Creates a new hasher for the chosen algorithm and salt.
+    ///
+    pub fn hasher(&self) -> Box<dyn Hasher> {
+        self.algorithm.hasher(self.salt)
+    }
+    ///
+    /// Returns the bucket the key goes in.
+    ///
+    pub fn bucket(&self, key: &[u8]) -> usize {
+        bucket(self.algorithm, self.salt, self.table_size, key)
+    }
+}
+
+fn bucket(algorithm: Algorithm, salt: u64, table_size: usize, key: &[u8]) -> usize {
+    (algorithm.hash(salt, key) % table_size as u64) as usize
+}
+
+///
+/// Iterates over the primes at or above the given number.
+///
+fn primes_from(start: u64) -> impl Iterator<Item = u64> {
+    (start..).filter(|&n| is_prime(n))
+}
+
+fn is_prime(n: u64) -> bool {
+    if n < 4 {
+        return n > 1;
+    }
+    if n % 2 == 0 {
+        return false;
+    }
+    let mut i = 3;
+    while i * i <= n {
+        if n % i == 0 {
+            return false;
+        }
+        i += 2;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_does_tune_correctly() {
+        // "Ez" and "FY" collide for every X33a salt so only X33x can win.
+        let keys = ["Ez", "FY"];
+        let sut = Tuner::new(3)
+            .algorithms(&[Algorithm::X33a, Algorithm::X33x])
+            .salts(vec![5381, 5387]);
+        let tuning = sut.tune(&keys);
+        assert_eq!(tuning.algorithm, Algorithm::X33x);
+        assert_eq!(tuning.salt, 5381);
+        assert_eq!(tuning.report.collisions, 0);
+        assert_eq!(tuning.report.max_load, 1);
+        assert_ne!(tuning.bucket(b"Ez"), tuning.bucket(b"FY"));
+        assert_eq!(tuning.runners_up.len(), 3);
+        assert_eq!(tuning.runners_up[0].algorithm, Algorithm::X33a);
+        assert_eq!(tuning.runners_up[0].collisions, 1);
+        assert_eq!(tuning.runners_up[2].algorithm, Algorithm::X33x);
+        assert_eq!(tuning.runners_up[2].salt, 5387);
+        let mut hasher = tuning.hasher();
+        hasher.write(b"Ez");
+        assert_eq!(hasher.finish(), 5861786u64);
+        let primes: Vec<u64> = primes_from(5381).take(3).collect();
+        assert_eq!(primes, vec![5381, 5387, 5393]);
+        // Tcl's hash has no salt so it is only tried once.
+        let tuning = Tuner::new(3)
+            .algorithms(&[Algorithm::TclHash])
+            .salts(vec![5381, 5387])
+            .tune(&keys);
+        assert_eq!(tuning.salt, 0);
+        assert!(tuning.runners_up.is_empty());
+    }
+}
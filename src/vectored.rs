@@ -0,0 +1,148 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+//!
+//! Hashing data kept in more than one buffer.
+//!
+//! Every hasher in the crate works through its input in order, a byte or a
+//! unit at a time, so writing the pieces of a message one after the other
+//! gives the same hash as writing all of it at once. `HasherVectored` does
+//! that for `IoSlice` lists, the two halves of a `VecDeque<u8>` ring buffer
+//! and any iterator of byte slices like a chain of segments, without first
+//! copying them into one buffer.
+//!
+//! The one exception is `fold::Fold` in Unicode mode, which can not fold a
+//! character whose UTF-8 bytes are split across two pieces. See its "Split
+//! characters" section.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use std::collections::VecDeque;
+//! use std::hash::Hasher;
+//! use std::io::IoSlice;
+//! use djb_hash::HasherVectored;
+//! use djb_hash::x33a_u32::X33aU32;
+//! let mut hasher = X33aU32::new();
+//! hasher.write_io_slices(&[IoSlice::new(b"mod_"), IoSlice::new(b"rewrite")]);
+//! let mut ring: VecDeque<u8> = VecDeque::with_capacity(8);
+//! ring.extend(b"xxxmod_r");
+//! ring.drain(..3);
+//! ring.extend(b"ewrite");
+//! let mut hasher2 = X33aU32::new();
+//! hasher2.write_vec_deque(&ring);
+//! assert_eq!(hasher.finish(), hasher2.finish());
+//! ```
+//!
+use std::collections::VecDeque;
+use std::hash::Hasher;
+use std::io::IoSlice;
+
+///
+/// This trait is used to write data kept in more than one buffer.
+///
+/// It is implemented for every `Hasher`, boxed ones included.
+///
+pub trait HasherVectored: Hasher {
+    ///
+    /// Writes each slice in turn.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::hash::Hasher;
+    /// use djb_hash::HasherVectored;
+    /// use djb_hash::x33a::X33a;
+    /// let segments = vec![b"Hello".to_vec(), b", ".to_vec(), b"World".to_vec()];
+    /// let mut hasher1 = X33a::new();
+    /// let mut hasher2 = X33a::new();
+    /// hasher1.write_slices(segments.iter().map(|s| &s[..]));
+    /// hasher2.write(b"Hello, World");
+    /// assert_eq!(hasher1.finish(), hasher2.finish());
+    /// ```
+    ///
+    fn write_slices<'a, I>(&mut self, slices: I)
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        for slice in slices {
+            self.write(slice);
+        }
+    }
+    ///
+    /// Writes each `IoSlice` in turn, the same buffers `write_vectored()`
+    /// takes.
+    ///
+    fn write_io_slices(&mut self, slices: &[IoSlice]) {
+        for slice in slices {
+            self.write(slice);
+        }
+    }
+    ///
+    /// Writes the contents of the ring buffer from front to back.
+    ///
+    fn write_vec_deque(&mut self, deque: &VecDeque<u8>) {
+        let (front, back) = deque.as_slices();
+        self.write(front);
+        self.write(back);
+    }
+}
+
+impl<H: Hasher + ?Sized> HasherVectored for H {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Algorithm;
+
+    #[test]
+    fn it_does_write_slices_correctly() {
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 7 % 256) as u8).collect();
+        let pieces: Vec<&[u8]> = data.chunks(37).collect();
+        let io_slices: Vec<IoSlice> = pieces.iter().map(|p| IoSlice::new(p)).collect();
+        let mut deque: VecDeque<u8> = VecDeque::with_capacity(data.len());
+        deque.extend(&data[..150]);
+        deque.extend(&data[..150]);
+        deque.drain(..150);
+        deque.extend(&data[150..]);
+        assert_ne!(deque.as_slices().1.len(), 0);
+        for algorithm in Algorithm::ALL.iter() {
+            let salt = algorithm.default_salt();
+            let expected = algorithm.hash(salt, &data);
+            let mut sut = algorithm.hasher(salt);
+            sut.write_slices(pieces.iter().cloned());
+            assert_eq!(sut.finish(), expected, "{}", algorithm);
+            let mut sut = algorithm.hasher(salt);
+            sut.write_io_slices(&io_slices);
+            assert_eq!(sut.finish(), expected, "{}", algorithm);
+            let mut sut = algorithm.hasher(salt);
+            sut.write_vec_deque(&deque);
+            assert_eq!(sut.finish(), expected, "{}", algorithm);
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// New BSD License
+//
+// Copyright © 2018-present, Michael Cummings <mgcummings@yahoo.com>.
+// All rights reserved.
+//
+// Redistribution and use in source and binary forms, with or without
+// modification, are permitted provided that the following conditions are met:
+//     * Redistributions of source code must retain the above copyright notice,
+//       this list of conditions and the following disclaimer.
+//     * Redistributions in binary form must reproduce the above copyright
+//       notice, this list of conditions and the following disclaimer in the
+//       documentation and/or other materials provided with the distribution.
+//     * Neither the name of the copyright holder nor the names of its
+//       contributors may be used to endorse or promote products derived from
+//       this software without specific prior written permission.
+//
+// THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS"
+// AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+// IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE
+// ARE DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDERS AND CONTRIBUTORS BE
+// LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR
+// CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF
+// SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+// INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN
+// CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE)
+// ARISING IN ANY WAY OUT OF THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE
+// POSSIBILITY OF SUCH DAMAGE.
+//
+use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{Checkpoint, CheckpointError, Resumable};
+use super::{HasherU32, HasherUnit};
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
+
+///
+/// Implements 32 bit version of one of the original hash functions post by Daniel J. Bernstein but
+/// treating each byte as a signed C `char`.
+///
+/// Classic C versions read the string through a `char` pointer which is signed on x86, so bytes
+/// of 0x80 and up are sign extended before being added. ASCII input gives the same hash as X33aU32
+/// but any other UTF-8 text will not.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::hash::Hasher;
+/// use djb_hash::HasherU32;
+/// use djb_hash::x33a_u32_signed::*;
+/// let input = "naïve";
+/// let mut hasher = X33aU32Signed::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish(), 254740161u64);
+/// assert_eq!(hasher.finish_u32(), 254740161u32);
+/// ```
+///
+/// The unsigned version gives a different hash for the same input:
+///
+/// ```rust
+/// # use std::hash::Hasher;
+/// # use djb_hash::x33a_u32::*;
+/// let input = "naïve";
+/// let mut hasher = X33aU32::new();
+/// hasher.write(&input.as_bytes());
+/// assert_eq!(hasher.finish(), 264218817u64);
+/// ```
+///
+pub struct X33aU32Signed {
+    hash: u32,
+    salt: u32,
+    consumed: u64,
+    int_encoding: IntEncoding,
+}
+
+impl X33aU32Signed {
+    ///
+    /// Creates a new hash using the original 5381 prime number salt value used by DJB.
+    ///
+    pub fn new() -> Self {
+        X33aU32Signed { hash: 5381, salt: 5381, consumed: 0, int_encoding: IntEncoding::LittleEndian }
+    }
+    ///
+    /// Creates a new hash using user supplied salt value.
+    ///
+    /// See X33aU32::new_with_salt() for the things to keep in mind when picking a salt.
+    ///
+    pub fn new_with_salt(s: u32) -> Self {
+        X33aU32Signed { hash: s, salt: s, consumed: 0, int_encoding: IntEncoding::LittleEndian }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for X33aU32Signed {
+    ///
+    /// Same as new(), using the original 5381 prime number salt value.
+    ///
+    fn default() -> Self {
+        X33aU32Signed::new()
+    }
+}
+
+impl HasherU32 for X33aU32Signed {
+    fn finish_u32(&self) -> u32 {
+        self.hash
+    }
+}
+
+impl HasherUnit for X33aU32Signed {
+    ///
+    /// Writes a code unit to hash the same way write() does a byte, except the
+    /// unit is not sign extended.
+    ///
+    fn write_unit(&mut self, unit: u32) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 5).wrapping_add(self.hash).wrapping_add(unit);
+    }
+}
+
+impl WriteIdentity for X33aU32Signed {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 5).wrapping_add(self.hash).wrapping_add(value as u32);
+    }
+}
+
+impl Hasher for X33aU32Signed {
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+    ///
+    /// Writes byte slice to hash.
+    ///
+    /// Does hash * 33 + byte but is implemented as hash << 5 (*32) + hash + byte as this is faster
+    /// on most processors vs normal multiplication. The byte is sign extended first.
+    ///
+    fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
+        for byte in bytes {
+            self.hash = (self.hash << 5).wrapping_add(self.hash).wrapping_add(*byte as i8 as u32);
+        }
+    }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for X33aU32Signed {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(Algorithm::X33aU32Signed, self.salt as u64, self.hash as u64, self.consumed, self.int_encoding)
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        checkpoint.expect(Algorithm::X33aU32Signed, 0)?;
+        Ok(X33aU32Signed {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            consumed: checkpoint.consumed,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use super::*;
+
+    #[test]
+    fn it_does_hash_correctly() {
+        let mut sut = X33aU32Signed::new();
+        let input = [69, 122];
+        sut.write(&input);
+        assert_eq!(sut.finish(), 5862308u64);
+        let mut sut = X33aU32Signed::new_with_salt(5381);
+        let input = "Grüße";
+        sut.write(input.as_bytes());
+        assert_eq!(sut.finish(), 1654414980u64);
+    }
+}
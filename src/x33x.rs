@@ -27,6 +27,11 @@
 // POSSIBILITY OF SUCH DAMAGE.
 //
 use std::hash::Hasher;
+use super::Algorithm;
+use super::checkpoint::{Checkpoint, CheckpointError, Resumable};
+use super::HasherUnit;
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
 
 ///
 /// Implements 64 bit version of one of the "improved" hash functions post by Daniel J. Bernstein.
@@ -58,6 +63,9 @@ use std::hash::Hasher;
 ///
 pub struct X33x {
     hash: u64,
+    salt: u64,
+    consumed: u64,
+    int_encoding: IntEncoding,
 }
 
 impl X33x {
@@ -65,7 +73,7 @@ impl X33x {
     /// Creates a new hash using the original 5381 prime number salt value used by DJB.
     ///
     pub fn new() -> Self {
-        X33x { hash: 5381 }
+        X33x { hash: 5381, salt: 5381, consumed: 0, int_encoding: IntEncoding::LittleEndian }
     }
     ///
     /// Creates a new hash using user supplied salt value.
@@ -78,7 +86,43 @@ impl X33x {
     /// work best in most cases and between 16 to 24 bits for 32 bit hashes.
     ///
     pub fn new_with_salt(s: u64) -> Self {
-        X33x { hash: s }
+        X33x { hash: s, salt: s, consumed: 0, int_encoding: IntEncoding::LittleEndian }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for X33x {
+    ///
+    /// Same as new(), using the original 5381 prime number salt value.
+    ///
+    fn default() -> Self {
+        X33x::new()
+    }
+}
+
+impl HasherUnit for X33x {
+    ///
+    /// Writes a code unit to hash the same way write() does a byte.
+    ///
+    fn write_unit(&mut self, unit: u32) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 5).wrapping_add(self.hash) ^ unit as u64;
+    }
+}
+
+impl WriteIdentity for X33x {
+    const BITS: u32 = 64;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 5).wrapping_add(self.hash) ^ value;
     }
 }
 
@@ -93,10 +137,27 @@ impl Hasher for X33x {
     /// this is faster on most processors vs normal multiplication.
     ///
     fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
         for byte in bytes {
             self.hash = (self.hash << 5).wrapping_add(self.hash) ^ *byte as u64;
         }
     }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for X33x {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(Algorithm::X33x, self.salt, self.hash, self.consumed, self.int_encoding)
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        checkpoint.expect(Algorithm::X33x, 0)?;
+        Ok(X33x {
+            hash: checkpoint.state,
+            salt: checkpoint.salt,
+            consumed: checkpoint.consumed,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -27,7 +27,11 @@
 // POSSIBILITY OF SUCH DAMAGE.
 //
 use std::hash::Hasher;
-use super::HasherU32;
+use super::Algorithm;
+use super::checkpoint::{Checkpoint, CheckpointError, Resumable};
+use super::{HasherU32, HasherUnit};
+use super::IntEncoding;
+use super::int_encoding::WriteIdentity;
 
 ///
 /// Implements 32 bit version of one of the "improved" hash functions post by Daniel J. Bernstein.
@@ -62,6 +66,9 @@ use super::HasherU32;
 ///
 pub struct X33xU32 {
     hash: u32,
+    salt: u32,
+    consumed: u64,
+    int_encoding: IntEncoding,
 }
 
 impl X33xU32 {
@@ -69,7 +76,7 @@ impl X33xU32 {
     /// Creates a new hash using the original 5381 prime number salt value used by DJB.
     ///
     pub fn new() -> Self {
-        X33xU32 { hash: 5381 }
+        X33xU32 { hash: 5381, salt: 5381, consumed: 0, int_encoding: IntEncoding::LittleEndian }
     }
     ///
     /// Creates a new hash using user supplied salt value.
@@ -82,7 +89,25 @@ impl X33xU32 {
     /// work best in most cases and between 16 to 24 bits for 32 bit hashes.
     ///
     pub fn new_with_salt(s: u32) -> Self {
-        X33xU32 { hash: s }
+        X33xU32 { hash: s, salt: s, consumed: 0, int_encoding: IntEncoding::LittleEndian }
+    }
+    ///
+    /// Sets how the integer write_*() functions add integers to the hash.
+    ///
+    /// The default is `IntEncoding::LittleEndian`.
+    ///
+    pub fn int_encoding(mut self, encoding: IntEncoding) -> Self {
+        self.int_encoding = encoding;
+        self
+    }
+}
+
+impl Default for X33xU32 {
+    ///
+    /// Same as new(), using the original 5381 prime number salt value.
+    ///
+    fn default() -> Self {
+        X33xU32::new()
     }
 }
 
@@ -92,6 +117,24 @@ impl HasherU32 for X33xU32 {
     }
 }
 
+impl HasherUnit for X33xU32 {
+    ///
+    /// Writes a code unit to hash the same way write() does a byte.
+    ///
+    fn write_unit(&mut self, unit: u32) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 5).wrapping_add(self.hash) ^ unit;
+    }
+}
+
+impl WriteIdentity for X33xU32 {
+    const BITS: u32 = 32;
+    fn write_identity(&mut self, value: u64) {
+        self.consumed = self.consumed.wrapping_add(1);
+        self.hash = (self.hash << 5).wrapping_add(self.hash) ^ value as u32;
+    }
+}
+
 impl Hasher for X33xU32 {
     fn finish(&self) -> u64 {
         self.hash as u64
@@ -103,13 +146,31 @@ impl Hasher for X33xU32 {
     /// this is faster on most processors vs normal multiplication.
     ///
     fn write(&mut self, bytes: &[u8]) {
+        self.consumed = self.consumed.wrapping_add(bytes.len() as u64);
         for byte in bytes {
             self.hash = (self.hash << 5).wrapping_add(self.hash) ^ *byte as u32;
         }
     }
+    write_int_functions!(int_encoding);
+}
+
+impl Resumable for X33xU32 {
+    fn checkpoint(&self) -> Checkpoint {
+        Checkpoint::new(Algorithm::X33xU32, self.salt as u64, self.hash as u64, self.consumed, self.int_encoding)
+    }
+    fn restore(checkpoint: &Checkpoint) -> Result<Self, CheckpointError> {
+        checkpoint.expect(Algorithm::X33xU32, 0)?;
+        Ok(X33xU32 {
+            hash: checkpoint.state as u32,
+            salt: checkpoint.salt as u32,
+            consumed: checkpoint.consumed,
+            int_encoding: checkpoint.int_encoding,
+        })
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_borrow)]
 mod tests {
     use std::hash::Hasher;
     use super::*;
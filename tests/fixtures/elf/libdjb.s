@@ -0,0 +1,159 @@
+# Source of the libdjb*.so fixtures. Built with GNU as and ld:
+#
+#   as --64 -o libdjb64.o libdjb.s
+#   ld -m elf_x86_64 -shared --hash-style=gnu -soname libdjb.so -s \
+#       -z noseparate-code -o libdjb64-gnu.so libdjb64.o
+#
+# and the same with --32 and -m elf_i386 for the 32 bit versions. The
+# libdjb*-sysv.so versions use --hash-style=sysv instead.
+	.text
+	.globl djb_alpha
+	.type djb_alpha, @function
+djb_alpha:
+	movl $0, %eax
+	ret
+	.globl djb_beta
+	.type djb_beta, @function
+djb_beta:
+	movl $1, %eax
+	ret
+	.globl djb_gamma
+	.type djb_gamma, @function
+djb_gamma:
+	movl $2, %eax
+	ret
+	.globl djb_delta
+	.type djb_delta, @function
+djb_delta:
+	movl $3, %eax
+	ret
+	.globl printf
+	.type printf, @function
+printf:
+	movl $4, %eax
+	ret
+	.globl malloc
+	.type malloc, @function
+malloc:
+	movl $5, %eax
+	ret
+	.globl free
+	.type free, @function
+free:
+	movl $6, %eax
+	ret
+	.globl strlen
+	.type strlen, @function
+strlen:
+	movl $7, %eax
+	ret
+	.globl memcpy
+	.type memcpy, @function
+memcpy:
+	movl $8, %eax
+	ret
+	.globl memset
+	.type memset, @function
+memset:
+	movl $9, %eax
+	ret
+	.globl open
+	.type open, @function
+open:
+	movl $10, %eax
+	ret
+	.globl close
+	.type close, @function
+close:
+	movl $11, %eax
+	ret
+	.globl read
+	.type read, @function
+read:
+	movl $12, %eax
+	ret
+	.globl write
+	.type write, @function
+write:
+	movl $13, %eax
+	ret
+	.globl dl_new_hash
+	.type dl_new_hash, @function
+dl_new_hash:
+	movl $14, %eax
+	ret
+	.globl _init
+	.type _init, @function
+_init:
+	movl $15, %eax
+	ret
+	.globl _fini
+	.type _fini, @function
+_fini:
+	movl $16, %eax
+	ret
+	.globl main
+	.type main, @function
+main:
+	movl $17, %eax
+	ret
+	.globl setup
+	.type setup, @function
+setup:
+	movl $18, %eax
+	ret
+	.globl teardown
+	.type teardown, @function
+teardown:
+	movl $19, %eax
+	ret
+	.globl Ez
+	.type Ez, @function
+Ez:
+	movl $20, %eax
+	ret
+	.globl FY
+	.type FY, @function
+FY:
+	movl $21, %eax
+	ret
+	.globl a
+	.type a, @function
+a:
+	movl $22, %eax
+	ret
+	.globl b
+	.type b, @function
+b:
+	movl $23, %eax
+	ret
+	.globl c
+	.type c, @function
+c:
+	movl $24, %eax
+	ret
+	.globl abcdefghijklmnopqrstuvwxyz
+	.type abcdefghijklmnopqrstuvwxyz, @function
+abcdefghijklmnopqrstuvwxyz:
+	movl $25, %eax
+	ret
+	.globl x33a
+	.type x33a, @function
+x33a:
+	movl $26, %eax
+	ret
+	.globl x33x
+	.type x33x, @function
+x33x:
+	movl $27, %eax
+	ret
+	.globl cdb_find
+	.type cdb_find, @function
+cdb_find:
+	movl $28, %eax
+	ret
+	.globl cdb_make
+	.type cdb_make, @function
+cdb_make:
+	movl $29, %eax
+	ret
@@ -0,0 +1,87 @@
+# Source of libdjb64-sysv16.so, which has 16 hashed symbols. Built with GNU as
+# and ld:
+#
+#   as --64 -o libdjb16.o libdjb16.s
+#   ld -m elf_x86_64 -shared --hash-style=sysv -soname libdjb16.so -s \
+#       -z noseparate-code -o libdjb64-sysv16.so libdjb16.o
+	.text
+	.globl djb_0
+	.type djb_0, @function
+djb_0:
+	movl $0, %eax
+	ret
+	.globl djb_1
+	.type djb_1, @function
+djb_1:
+	movl $1, %eax
+	ret
+	.globl djb_2
+	.type djb_2, @function
+djb_2:
+	movl $2, %eax
+	ret
+	.globl djb_3
+	.type djb_3, @function
+djb_3:
+	movl $3, %eax
+	ret
+	.globl djb_4
+	.type djb_4, @function
+djb_4:
+	movl $4, %eax
+	ret
+	.globl djb_5
+	.type djb_5, @function
+djb_5:
+	movl $5, %eax
+	ret
+	.globl djb_6
+	.type djb_6, @function
+djb_6:
+	movl $6, %eax
+	ret
+	.globl djb_7
+	.type djb_7, @function
+djb_7:
+	movl $7, %eax
+	ret
+	.globl djb_8
+	.type djb_8, @function
+djb_8:
+	movl $8, %eax
+	ret
+	.globl djb_9
+	.type djb_9, @function
+djb_9:
+	movl $9, %eax
+	ret
+	.globl djb_10
+	.type djb_10, @function
+djb_10:
+	movl $10, %eax
+	ret
+	.globl djb_11
+	.type djb_11, @function
+djb_11:
+	movl $11, %eax
+	ret
+	.globl djb_12
+	.type djb_12, @function
+djb_12:
+	movl $12, %eax
+	ret
+	.globl djb_13
+	.type djb_13, @function
+djb_13:
+	movl $13, %eax
+	ret
+	.globl djb_14
+	.type djb_14, @function
+djb_14:
+	movl $14, %eax
+	ret
+	.globl djb_15
+	.type djb_15, @function
+djb_15:
+	movl $15, %eax
+	ret
@@ -0,0 +1,17 @@
+# Source of libdjb64-sysv2.so, which has 2 hashed symbols. Built with GNU as
+# and ld:
+#
+#   as --64 -o libdjb2.o libdjb2.s
+#   ld -m elf_x86_64 -shared --hash-style=sysv -soname libdjb2.so -s \
+#       -z noseparate-code -o libdjb64-sysv2.so libdjb2.o
+	.text
+	.globl djb_0
+	.type djb_0, @function
+djb_0:
+	movl $0, %eax
+	ret
+	.globl djb_1
+	.type djb_1, @function
+djb_1:
+	movl $1, %eax
+	ret